@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ResultExt},
+    model::Drug,
+    predicate::Predicate,
+    quarantine::Quarantine,
+    source::typed_records,
+    table::{save_to_file, Cell, Field, Kind, Tabular},
+};
+
+/// A secondary or legacy drugbank id and the primary id it stands for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alias {
+    pub alias: String,
+    pub drugbank_id: String,
+}
+
+impl Tabular for Alias {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("alias", Kind::Utf8),
+            Field::new("drugbank_id", Kind::Utf8),
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![Cell::text(&self.alias), Cell::text(&self.drugbank_id)]
+    }
+}
+
+/// Resolves any drugbank id of a release, primary or alias, to the primary
+/// id.
+#[derive(Debug, Clone, Default)]
+pub struct AliasIndex {
+    primary: HashMap<String, String>,
+}
+
+impl AliasIndex {
+    pub fn add(&mut self, drug: &Drug) {
+        self.insert(drug.primary_id(), &drug.aliases());
+    }
+
+    fn insert(&mut self, id: &str, aliases: &[String]) {
+        if id.is_empty() {
+            return;
+        }
+        self.primary.insert(id.to_string(), id.to_string());
+        for alias in aliases {
+            match self.primary.get(alias) {
+                Some(other) if other != id => {
+                    log::warn!(
+                        "{} is an alias of {} and {}, keeping {}",
+                        alias,
+                        other,
+                        id,
+                        other
+                    );
+                }
+                _ => {
+                    self.primary.insert(alias.clone(), id.to_string());
+                }
+            }
+        }
+    }
+
+    /// Index of every drug of `input`, a split directory or an NDJSON
+    /// stream, that matches `predicate`. Drugs that can't be read are
+    /// quarantined and skipped.
+    pub fn from_input(
+        input: &str,
+        predicate: Option<&Predicate>,
+        quarantine: &Quarantine,
+    ) -> Result<Self> {
+        let drugs = typed_records(input)?
+            .par_bridge()
+            .map(|record| {
+                Ok(record?
+                    .read::<Drug>(quarantine)
+                    .filter(|drug| predicate.is_none_or(|p| p.matches(drug)))
+                    .map(|drug| (drug.primary_id().to_string(), drug.aliases())))
+            })
+            .collect::<Result<Vec<Option<(String, Vec<String>)>>>>()?;
+
+        // primary ids go first so an alias can't shadow one
+        let mut drugs: Vec<(String, Vec<String>)> = drugs.into_iter().flatten().collect();
+        drugs.sort();
+        let mut index = AliasIndex::default();
+        for (id, _) in &drugs {
+            index.insert(id, &[]);
+        }
+        for (id, aliases) in &drugs {
+            index.insert(id, aliases);
+        }
+
+        Ok(index)
+    }
+
+    /// Reads an alias CSV written by `aliases_csv`.
+    pub fn from_csv(path: &str) -> Result<Self> {
+        let mut reader = csv::Reader::from_path(path).file(path)?;
+        let mut index = AliasIndex::default();
+        for row in reader.deserialize() {
+            let alias: Alias = row.file(path)?;
+            index
+                .primary
+                .insert(alias.drugbank_id.clone(), alias.drugbank_id.clone());
+            index.primary.insert(alias.alias, alias.drugbank_id);
+        }
+        Ok(index)
+    }
+
+    /// The primary id of `id`, itself when it is a primary id.
+    pub fn resolve(&self, id: &str) -> Option<&str> {
+        self.primary.get(id.trim()).map(|f| f.as_str())
+    }
+
+    /// Aliases sorted by alias.
+    pub fn aliases(&self) -> Vec<Alias> {
+        let mut aliases: Vec<Alias> = self
+            .primary
+            .iter()
+            .filter(|(alias, id)| alias != id)
+            .map(|(alias, id)| Alias {
+                alias: alias.clone(),
+                drugbank_id: id.clone(),
+            })
+            .collect();
+        aliases.sort_by(|a, b| a.alias.cmp(&b.alias));
+        aliases
+    }
+}
+
+/// Writes the alias to primary id table of every drug of `input`, a split
+/// directory or an NDJSON stream, that matches `predicate`, as Parquet when
+/// `output` ends in `.parquet` and CSV otherwise. Returns the number of
+/// aliases written.
+pub fn aliases_csv(
+    input: &str,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let aliases = AliasIndex::from_input(input, predicate, quarantine)?.aliases();
+    save_to_file(output, &aliases)?;
+
+    Ok(aliases.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_index() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let json = dir.join("json");
+        let json = json.to_str().unwrap();
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            json,
+            0,
+            &Default::default(),
+            &Quarantine::default(),
+        )
+        .unwrap();
+
+        let index = AliasIndex::from_input(json, None, &Quarantine::default()).unwrap();
+        assert_eq!(index.resolve("BTD00024"), Some("DB00001"));
+        assert_eq!(index.resolve("APRD00379"), Some("DB00006"));
+        assert_eq!(index.resolve("DB00002"), Some("DB00002"));
+        assert_eq!(index.resolve("DB99999"), None);
+
+        let output = dir.join("aliases.csv");
+        let output = output.to_str().unwrap();
+        assert_eq!(
+            aliases_csv(json, output, None, &Quarantine::default()).unwrap(),
+            4
+        );
+        let csv = std::fs::read_to_string(output).unwrap();
+        assert!(csv.starts_with("alias,drugbank_id\nAPRD00002,DB00002\n"));
+
+        let index = AliasIndex::from_csv(output).unwrap();
+        assert_eq!(index.resolve("BIOD00024"), Some("DB00001"));
+        assert_eq!(index.resolve("DB00006"), Some("DB00006"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
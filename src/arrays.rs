@@ -0,0 +1,114 @@
+use serde_json::Value;
+
+/// Element paths whose values are always written as JSON arrays, so a
+/// single `<group>` comes out as `["approved"]` just like several do.
+///
+/// Paths are `/` separated element names starting at the drug element,
+/// `drug/groups/group`. `*` stands for one or more elements, `*/polypeptide`
+/// matches polypeptides at any depth.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArrayPaths(Vec<Vec<String>>);
+
+impl ArrayPaths {
+    pub fn new<S: AsRef<str>>(paths: &[S]) -> Self {
+        ArrayPaths(
+            paths
+                .iter()
+                .map(|f| {
+                    f.as_ref()
+                        .split('/')
+                        .filter(|f| !f.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .filter(|f: &Vec<String>| !f.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn matches(&self, path: &[&str]) -> bool {
+        self.0.iter().any(|f| matches(f, path))
+    }
+
+    /// Wraps the values at matching paths below `value`, the element named
+    /// `name`, in arrays.
+    pub fn apply(&self, name: &str, value: &mut Value) {
+        if !self.is_empty() {
+            self.walk(&mut vec![name], value);
+        }
+    }
+
+    fn walk<'a>(&self, path: &mut Vec<&'a str>, value: &'a mut Value) {
+        match value {
+            Value::Object(children) => {
+                for (key, child) in children.iter_mut() {
+                    path.push(key);
+                    if !child.is_array() && self.matches(path) {
+                        *child = Value::Array(vec![child.take()]);
+                    }
+                    self.walk(path, child);
+                    path.pop();
+                }
+            }
+            // repeated elements share the path of the array
+            Value::Array(items) => items.iter_mut().for_each(|f| self.walk(path, f)),
+            _ => {}
+        }
+    }
+}
+
+fn matches(pattern: &[String], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((p, rest)), Some((_, tail))) if p == "*" => {
+            matches(rest, tail) || matches(pattern, tail)
+        }
+        (Some((p, rest)), Some((s, tail))) => p == s && matches(rest, tail),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_array_paths() {
+        let paths = ArrayPaths::new(&["drug/groups/group", "*/polypeptide", "/drug/*/action"]);
+        assert!(paths.matches(&["drug", "groups", "group"]));
+        assert!(paths.matches(&["drug", "targets", "target", "polypeptide"]));
+        assert!(paths.matches(&["drug", "targets", "target", "actions", "action"]));
+        assert!(!paths.matches(&["polypeptide"]));
+        assert!(!paths.matches(&["drug", "groups"]));
+
+        let mut drug = json!({
+            "groups": {"group": "approved"},
+            "targets": {"target": [
+                {"polypeptide": {"@id": "P00734"}, "actions": {"action": "inhibitor"}},
+                {"polypeptide": [{"@id": "P08514"}, {"@id": "P05106"}], "actions": {}},
+            ]},
+        });
+        paths.apply("drug", &mut drug);
+
+        assert_eq!(drug["groups"]["group"], json!(["approved"]));
+        assert_eq!(
+            drug["targets"]["target"][0]["polypeptide"],
+            json!([{"@id": "P00734"}])
+        );
+        assert_eq!(
+            drug["targets"]["target"][0]["actions"]["action"],
+            json!(["inhibitor"])
+        );
+        assert_eq!(
+            drug["targets"]["target"][1]["polypeptide"][1]["@id"],
+            "P05106"
+        );
+        assert_eq!(drug["targets"]["target"][1]["actions"], json!({}));
+    }
+}
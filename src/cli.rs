@@ -0,0 +1,554 @@
+use clap::{Args, Parser, Subcommand};
+use log::LevelFilter;
+
+use crate::{
+    config::{
+        has_credentials, Conversion, DrugType, EmptyElements, MongoSettings, Settings, ValueType,
+    },
+    error::{Error, Result},
+    predicate::Predicate,
+    quarantine::Quarantine,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "xml2csv",
+    version,
+    about = "Convert DrugBank XML into JSON, CSV and MongoDB"
+)]
+pub struct Cli {
+    /// Log level: off, error, warn, info, debug or trace
+    #[arg(long, global = true, default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// TOML config file, defaults to xml2csv.toml when it exists
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Directory failing records and the error report are written to
+    #[arg(long, global = true, default_value = "data/quarantine")]
+    pub quarantine: String,
+
+    /// Exit with an error when more records than this fail
+    #[arg(long, global = true)]
+    pub max_failures: Option<usize>,
+
+    /// Worker threads for parsing, defaults to one per CPU
+    #[arg(long, global = true)]
+    pub threads: Option<usize>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Split a DrugBank XML dump into one JSON file per drug
+    Split {
+        /// DrugBank XML dump, compressed (.gz/.zst/.bz2) or the release .zip
+        #[arg(short, long, default_value = "data/full_database.xml")]
+        input: String,
+        /// Directory the drug json files are written to, or an .ndjson/.jsonl
+        /// file with optional .gz/.zst to write a single stream
+        #[arg(short, long, default_value = "data/json")]
+        output: String,
+        /// Put files into sub directories named by the first N characters of
+        /// the drugbank id, e.g. 5 for DB000/DB00001.json
+        #[arg(long, default_value_t = 0)]
+        shard: usize,
+        #[command(flatten)]
+        conversion: ConversionArgs,
+    },
+    /// Extract SMILES/InChI of small molecules and the protein properties of
+    /// biotech drugs into MongoDB
+    Filter {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// Drugs to extract, overrides filter.drug_type of the config, small
+        /// molecules by default
+        #[arg(long, value_name = "small_molecule|biotech|all")]
+        drug_type: Option<DrugType>,
+        /// Write the records to this CSV/Parquet file instead of MongoDB
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Save to `sqlite:<file>`, `jsonl:<dir>` or `memory` instead of
+        /// MongoDB
+        #[arg(long)]
+        store: Option<String>,
+        /// Records per bulk upsert
+        #[arg(long, default_value_t = 500)]
+        batch: usize,
+        #[command(flatten)]
+        mongo: MongoArgs,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write the drug name / target gene / toxicity CSV
+    TargetsCsv {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// CSV file to write, or Parquet when it ends in .parquet
+        #[arg(short, long, default_value = "data/target_gene.csv")]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write one row per drug–target–polypeptide with UniProt ids, organism and actions
+    DrugTargets {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// CSV file to write, or Parquet when it ends in .parquet
+        #[arg(short, long, default_value = "data/drug_targets.csv")]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write the deduplicated, undirected drug–drug interaction edge list
+    Interactions {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// CSV file to write, or Parquet when it ends in .parquet
+        #[arg(short, long, default_value = "data/interactions.csv")]
+        output: String,
+        /// Leave out interactions with drugs that aren't part of the release
+        #[arg(long)]
+        drop_missing: bool,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write the sequences of biotech drugs as FASTA or CSV
+    Sequences {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// FASTA file when it ends in .fasta/.fa/.faa, optionally compressed,
+        /// Parquet when it ends in .parquet, CSV otherwise
+        #[arg(short, long, default_value = "data/sequences.fasta")]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write the table of legacy and secondary drugbank ids and the primary id
+    /// each stands for
+    Aliases {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// CSV file to write, or Parquet when it ends in .parquet
+        #[arg(short, long, default_value = "data/aliases.csv")]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write drugs, identifiers, groups, properties, targets, polypeptides and
+    /// interactions into a new SQLite database
+    Sqlite {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// SQLite database file, replaced if it exists
+        #[arg(short, long, default_value = "data/drugbank.sqlite")]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Write a CSV whose columns are described by a YAML/JSON mapping file
+    Csv {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// Mapping file, see mappings/target_gene.yaml
+        #[arg(short, long)]
+        mapping: String,
+        /// CSV file to write, or Parquet when it ends in .parquet
+        #[arg(short, long)]
+        output: String,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Load the split drug json files into a MongoDB collection as-is
+    LoadMongo {
+        /// Directory of split drug json files or an NDJSON stream
+        #[arg(short, long, default_value = "data/json")]
+        input: String,
+        /// Collection the drugs are inserted into, defaults to
+        /// collections.drugs of the config
+        #[arg(short, long)]
+        collection: Option<String>,
+        /// Documents per insert
+        #[arg(long, default_value_t = 500)]
+        batch: usize,
+        #[command(flatten)]
+        mongo: MongoArgs,
+        #[command(flatten)]
+        selection: WhereArgs,
+    },
+    /// Generate serde model structs from the DrugBank XSD
+    Codegen {
+        /// DrugBank XML schema
+        #[arg(short, long, default_value = "schema/drugbank.xsd")]
+        input: String,
+        /// Rust source file to write, the crate's model by default
+        #[arg(short, long, default_value = "src/model/generated.rs")]
+        output: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct MongoArgs {
+    /// MongoDB connection string, overrides mongo.url of the config
+    #[arg(long)]
+    pub mongo_url: Option<String>,
+}
+
+impl MongoArgs {
+    fn settings(&self, settings: &Settings) -> Result<MongoSettings> {
+        let mut mongo = settings.mongo.clone();
+        if let Some(url) = &self.mongo_url {
+            // command lines end up in shell history and process lists
+            if has_credentials(url) {
+                return Err(Error::Config("--mongo-url must not contain credentials, use XML2CSV_MONGO_USERNAME/PASSWORD or a secrets file".to_string()));
+            }
+            mongo.url = url.clone();
+        }
+        Ok(mongo)
+    }
+}
+
+/// Drug selection of the exports.
+#[derive(Debug, Args)]
+pub struct WhereArgs {
+    /// Only drugs matching this expression, e.g. `approved and not withdrawn
+    /// and average-mass < 500 and has InChIKey` or `logP between 0 and 5`
+    #[arg(long = "where", value_name = "EXPRESSION")]
+    pub expression: Option<String>,
+}
+
+impl WhereArgs {
+    fn predicate(&self) -> Result<Option<Predicate>> {
+        let predicate = self
+            .expression
+            .as_deref()
+            .map(Predicate::parse)
+            .transpose()?;
+        if let Some(expression) = &self.expression {
+            log::info!("selecting drugs where {}", expression);
+        }
+        Ok(predicate)
+    }
+}
+
+/// Overrides of the `[conversion]` config section.
+#[derive(Debug, Args)]
+pub struct ConversionArgs {
+    /// Prefix of attribute keys, `@` by default
+    #[arg(long)]
+    pub attribute_prefix: Option<String>,
+    /// Key of the text of elements with attributes, `#text` by default
+    #[arg(long)]
+    pub text_key: Option<String>,
+    /// Empty elements become an empty object, null or are left out
+    #[arg(long, value_name = "object|null|ignore")]
+    pub empty_elements: Option<EmptyElements>,
+    /// Keep text with leading zeros such as 007 as a string
+    #[arg(long)]
+    pub leading_zero_as_string: bool,
+    /// Keep the values at this path, e.g. `cas-number` or
+    /// `drugbank-id/@primary`, as strings, may be repeated
+    #[arg(long, value_name = "PATH")]
+    pub force_string: Vec<String>,
+    /// Always write the elements at this path as an array, e.g.
+    /// `drug/groups/group` or `*/polypeptide`, may be repeated
+    #[arg(long, value_name = "PATH")]
+    pub force_array: Vec<String>,
+}
+
+impl ConversionArgs {
+    fn conversion(&self, settings: &Settings) -> Conversion {
+        let mut conversion = settings.conversion.clone();
+        if let Some(prefix) = &self.attribute_prefix {
+            conversion.attribute_prefix = prefix.clone();
+        }
+        if let Some(key) = &self.text_key {
+            conversion.text_key = key.clone();
+        }
+        if let Some(empty) = self.empty_elements {
+            conversion.empty_elements = empty;
+        }
+        conversion.leading_zero_as_string |= self.leading_zero_as_string;
+        for path in &self.force_string {
+            conversion.types.insert(path.clone(), ValueType::String);
+        }
+        conversion.arrays.extend(self.force_array.iter().cloned());
+        conversion
+    }
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let settings = Settings::load(cli.config.as_deref())?;
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| Error::Config(format!("--threads : {}", e)))?;
+    }
+    let quarantine = Quarantine::new(Some(&cli.quarantine), cli.max_failures)?;
+
+    // the report is written even when the command itself failed
+    let result = execute(cli.command, &settings, &quarantine);
+    let finished = quarantine.finish();
+
+    result.and(finished)
+}
+
+fn execute(command: Command, settings: &Settings, quarantine: &Quarantine) -> Result<()> {
+    match command {
+        Command::Split {
+            input,
+            output,
+            shard,
+            conversion,
+        } => {
+            let conversion = conversion.conversion(settings);
+            let count = crate::split::xml2json(&input, &output, shard, &conversion, quarantine)?;
+            log::info!("split {} drugs into {}", count, output);
+        }
+        Command::Filter {
+            input,
+            drug_type,
+            output: Some(output),
+            selection,
+            ..
+        } => {
+            let drug_type = drug_type.unwrap_or(settings.filter.drug_type);
+            let count = crate::filter::filter_to_file(
+                &input,
+                &output,
+                drug_type,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} drugs to {}", count, output);
+        }
+        Command::Filter {
+            input,
+            drug_type,
+            store,
+            batch,
+            mongo,
+            selection,
+            ..
+        } => {
+            let predicate = selection.predicate()?;
+            let mongo = mongo.settings(settings)?;
+            let store = crate::store::open_store(store.as_deref().unwrap_or(&mongo.url), &mongo)?;
+            let result = crate::filter::start_filter(
+                &input,
+                store.as_ref(),
+                &settings.collections.filter,
+                batch,
+                drug_type.unwrap_or(settings.filter.drug_type),
+                predicate.as_ref(),
+                quarantine,
+            )?;
+            log::info!(
+                "saved drugs: inserted {}, modified {}, failed {}",
+                result.inserted,
+                result.modified,
+                result.failed
+            );
+        }
+        Command::TargetsCsv {
+            input,
+            output,
+            selection,
+        } => {
+            let count = crate::filter_target::targets_csv(
+                &input,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} drugs to {}", count, output);
+        }
+        Command::DrugTargets {
+            input,
+            output,
+            selection,
+        } => {
+            let count = crate::filter_target::drug_targets_csv(
+                &input,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} drug targets to {}", count, output);
+        }
+        Command::Interactions {
+            input,
+            output,
+            drop_missing,
+            selection,
+        } => {
+            let count = crate::interaction::interactions_csv(
+                &input,
+                &output,
+                drop_missing,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} interactions to {}", count, output);
+        }
+        Command::Sequences {
+            input,
+            output,
+            selection,
+        } => {
+            let count = crate::sequence::sequences_to_file(
+                &input,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} sequences to {}", count, output);
+        }
+        Command::Aliases {
+            input,
+            output,
+            selection,
+        } => {
+            let count = crate::alias::aliases_csv(
+                &input,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} aliases to {}", count, output);
+        }
+        Command::Sqlite {
+            input,
+            output,
+            selection,
+        } => {
+            let count = crate::sqlite::export_sqlite(
+                &input,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} drugs to {}", count, output);
+        }
+        Command::Csv {
+            input,
+            mapping,
+            output,
+            selection,
+        } => {
+            let mapping = crate::mapping::Mapping::from_path(&mapping)?;
+            let count = crate::mapping::mapping_csv(
+                &input,
+                &mapping,
+                &output,
+                selection.predicate()?.as_ref(),
+                quarantine,
+            )?;
+            log::info!("wrote {} rows to {}", count, output);
+        }
+        Command::LoadMongo {
+            input,
+            collection,
+            batch,
+            mongo,
+            selection,
+        } => {
+            let predicate = selection.predicate()?;
+            crate::db::init_db(&mongo.settings(settings)?)?;
+            let collection = collection.unwrap_or_else(|| settings.collections.drugs.clone());
+            let count = crate::load::load_mongo(
+                &input,
+                &collection,
+                batch,
+                predicate.as_ref(),
+                quarantine,
+            )?;
+            log::info!("loaded {} drugs into {}", count, collection);
+        }
+        Command::Codegen { input, output } => {
+            let count = crate::codegen::codegen(&input, &output)?;
+            log::info!("wrote {} structs to {}", count, output);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::try_parse_from([
+            "xml2csv",
+            "--log-level",
+            "debug",
+            "--threads",
+            "4",
+            "load-mongo",
+            "-i",
+            "out/json",
+            "--mongo-url",
+            "mongodb://db:27017",
+            "--where",
+            "approved and logP > 1",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.log_level, LevelFilter::Debug);
+        assert_eq!(cli.threads, Some(4));
+        match cli.command {
+            Command::LoadMongo {
+                input,
+                collection,
+                batch,
+                mongo,
+                selection,
+            } => {
+                assert_eq!(input, "out/json");
+                assert_eq!(collection, None);
+                assert_eq!(batch, 500);
+                assert_eq!(mongo.mongo_url.as_deref(), Some("mongodb://db:27017"));
+                assert!(selection.predicate().unwrap().is_some());
+            }
+            c => panic!("unexpected command {:?}", c),
+        }
+    }
+
+    #[test]
+    fn test_split_command() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let quarantine = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let cli = Cli::try_parse_from([
+            "xml2csv",
+            "split",
+            "--input",
+            "tests/fixtures/drugbank_sample.xml",
+            "--output",
+            dir.to_str().unwrap(),
+            "--quarantine",
+            quarantine.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        run(cli).unwrap();
+        // three drugs and the manifest
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_dir_all(quarantine);
+    }
+}
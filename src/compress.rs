@@ -0,0 +1,210 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use bzip2::{read::MultiBzDecoder, write::BzEncoder};
+use flate2::{
+    read::{DeflateDecoder, MultiGzDecoder},
+    write::GzEncoder,
+};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::error::{Error, Result};
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Compression of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Zip,
+}
+
+impl Compression {
+    /// From the magic bytes at the start of a file.
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if head.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if head.starts_with(BZIP2_MAGIC) {
+            Compression::Bzip2
+        } else if head.starts_with(ZIP_MAGIC) {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+
+    /// From a `.gz`, `.zst` or `.bz2` extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|f| f.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Opens `path` for reading, decompressing gzip, zstd and bzip2 files and
+/// reading zip archives by their magic bytes. See `zip_entry` for the member
+/// of an archive that is read.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead + Send>> {
+    let path = path.as_ref();
+    let mut file = BufReader::new(File::open(path).map_err(Error::io(path))?);
+    let compression = Compression::detect(file.fill_buf().map_err(Error::io(path))?);
+    if compression != Compression::None {
+        log::info!("reading {} as {:?}", path.display(), compression);
+    }
+
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        Compression::Zstd => Box::new(BufReader::new(
+            zstd::Decoder::with_buffer(file).map_err(Error::io(path))?,
+        )),
+        Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(file))),
+        Compression::Zip => Box::new(BufReader::new(zip_entry(path, file.into_inner())?)),
+    })
+}
+
+/// Streams one member of a zip archive: the largest `.xml` one, or the
+/// largest file when there is no XML, so readmes and schemas next to the
+/// dump are skipped.
+fn zip_entry(path: &Path, file: File) -> Result<Box<dyn Read + Send>> {
+    let name = path.display().to_string();
+    let zip_error = |e: zip::result::ZipError| Error::Config(format!("{} : {}", name, e));
+
+    let mut archive = ZipArchive::new(file).map_err(zip_error)?;
+    let mut entries = vec![];
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(zip_error)?;
+        if entry.is_dir() || entry.name().starts_with("__MACOSX/") {
+            continue;
+        }
+        let xml = entry.name().to_ascii_lowercase().ends_with(".xml");
+        entries.push((xml, entry.size(), i));
+    }
+
+    let (_, _, index) = entries
+        .into_iter()
+        .max()
+        .ok_or_else(|| Error::Config(format!("{} : empty zip archive", name)))?;
+    // the entry borrows the archive, read the data through the file instead
+    let (method, start, size) = {
+        let entry = archive.by_index_raw(index).map_err(zip_error)?;
+        log::info!("reading {} from {}", entry.name(), name);
+        (
+            entry.compression(),
+            entry.data_start(),
+            entry.compressed_size(),
+        )
+    };
+    let mut file = archive.into_inner();
+    file.seek(SeekFrom::Start(start)).map_err(Error::io(path))?;
+    let data = file.take(size);
+
+    match method {
+        CompressionMethod::Stored => Ok(Box::new(data)),
+        CompressionMethod::Deflated => Ok(Box::new(DeflateDecoder::new(data))),
+        m => Err(Error::Config(format!(
+            "{} : unsupported zip compression {}",
+            name, m
+        ))),
+    }
+}
+
+/// File writer compressing by the extension of its path, `finish` has to be
+/// called to write the end of the stream.
+pub enum Writer {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+    Bzip2(BzEncoder<BufWriter<File>>),
+}
+
+impl Writer {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = BufWriter::new(File::create(path).map_err(Error::io(path))?);
+
+        Ok(match Compression::from_path(path) {
+            // archives aren't written, `from_path` doesn't return zip anyway
+            Compression::None | Compression::Zip => Writer::Plain(file),
+            Compression::Gzip => Writer::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+            Compression::Zstd => {
+                Writer::Zstd(zstd::Encoder::new(file, 0).map_err(Error::io(path))?)
+            }
+            Compression::Bzip2 => {
+                Writer::Bzip2(BzEncoder::new(file, bzip2::Compression::default()))
+            }
+        })
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Writer::Plain(mut f) => f.flush(),
+            Writer::Gzip(f) => f.finish()?.flush(),
+            Writer::Zstd(f) => f.finish()?.flush(),
+            Writer::Bzip2(f) => f.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Writer::Plain(f) => f.write(buf),
+            Writer::Gzip(f) => f.write(buf),
+            Writer::Zstd(f) => f.write(buf),
+            Writer::Bzip2(f) => f.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Plain(f) => f.flush(),
+            Writer::Gzip(f) => f.flush(),
+            Writer::Zstd(f) => f.flush(),
+            Writer::Bzip2(f) => f.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    fn read_to_string(path: &Path) -> String {
+        let mut s = String::new();
+        open(path).unwrap().read_to_string(&mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for ext in ["txt", "gz", "zst", "bz2"] {
+            let path = std::env::temp_dir().join(format!("{}.{}", uuid::Uuid::new_v4(), ext));
+            let mut writer = Writer::create(&path).unwrap();
+            writer.write_all(b"{\"a\": 1}\n{\"a\": 2}\n").unwrap();
+            writer.finish().unwrap();
+
+            let head = std::fs::read(&path).unwrap();
+            assert_eq!(Compression::detect(&head), Compression::from_path(&path));
+            assert_eq!(read_to_string(&path), "{\"a\": 1}\n{\"a\": 2}\n");
+
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
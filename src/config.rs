@@ -1,45 +1,526 @@
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
 use log::LevelFilter;
 use log4rs::{
     append::{console::ConsoleAppender, file::FileAppender},
     config::{Appender, Root},
     encode::pattern::PatternEncoder,
 };
+use serde::Deserialize;
+
+use crate::error::{Error, Result, ResultExt};
 
-fn init_log() {
+fn init_log(level: LevelFilter) {
     let stdout = ConsoleAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{d} - {m}{n}")))
         .build();
 
-    let file = FileAppender::builder()
+    let mut builder =
+        log4rs::Config::builder().appender(Appender::builder().build("stdout", Box::new(stdout)));
+
+    // an unwritable log directory shouldn't stop the run
+    match FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{d} - {m}{n}")))
         .build(format!(
             "log/log_{}.log",
             chrono::Utc::now().timestamp_millis()
-        ))
-        .unwrap();
+        )) {
+        Ok(file) => builder = builder.appender(Appender::builder().build("file", Box::new(file))),
+        Err(e) => eprintln!("init log file error : {}", e),
+    }
 
-    let config = log4rs::Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .appender(Appender::builder().build("file", Box::new(file)))
-        .build(
-            Root::builder()
-                .appender("stdout")
-                // .appender("file")
-                .build(LevelFilter::Info),
-        )
-        .unwrap();
+    let config = builder.build(
+        Root::builder()
+            .appender("stdout")
+            // .appender("file")
+            .build(level),
+    );
 
-    let result = log4rs::init_config(config);
+    let result = config
+        .map_err(|e| e.to_string())
+        .and_then(|c| log4rs::init_config(c).map_err(|e| e.to_string()));
     if result.is_err() {
         print!("init log error : {:?}", result);
     }
 }
 
 pub fn init_config() {
+    init_config_with_level(LevelFilter::Info);
+}
+
+pub fn init_config_with_level(level: LevelFilter) {
     // let r = log4rs::init_file("config/log4rs.yaml", Default::default());
 
     // if r.is_err() {
     //     let _ = log4rs::init_file("rust/config/log4rs.yaml", Default::default());
     // }
-    init_log();
+    init_log(level);
+}
+
+/// Config file read when `--config` isn't given, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "xml2csv.toml";
+
+const ENV_PREFIX: &str = "XML2CSV_";
+
+/// Runtime settings: defaults, overridden by the TOML config file, overridden
+/// by `XML2CSV_*` environment variables.
+///
+/// Credentials never come from the config file, only from
+/// `XML2CSV_MONGO_USERNAME`/`XML2CSV_MONGO_PASSWORD` or the secrets file named
+/// by `secrets_file`/`XML2CSV_SECRETS_FILE`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub mongo: MongoSettings,
+    pub collections: Collections,
+    pub conversion: Conversion,
+    pub filter: FilterSettings,
+    /// TOML file holding `username` and `password`.
+    pub secrets_file: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MongoSettings {
+    pub url: String,
+    pub database: String,
+    pub connect_timeout_secs: u64,
+    pub server_selection_timeout_secs: u64,
+    #[serde(skip)]
+    pub username: Option<String>,
+    #[serde(skip)]
+    pub password: Option<String>,
+}
+
+impl Default for MongoSettings {
+    fn default() -> Self {
+        Self {
+            url: "mongodb://localhost:27017".to_string(),
+            database: "drugbank".to_string(),
+            connect_timeout_secs: 4,
+            server_selection_timeout_secs: 8,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Collections {
+    /// Filter records of `filter`.
+    pub filter: String,
+    /// Raw drugs of `load-mongo`.
+    pub drugs: String,
+}
+
+impl Default for Collections {
+    fn default() -> Self {
+        Self {
+            filter: "filter_properties".to_string(),
+            drugs: "drugs".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterSettings {
+    /// Drugs `filter` writes records of.
+    pub drug_type: DrugType,
+}
+
+/// Kinds of drugs selected by their `type` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrugType {
+    #[default]
+    SmallMolecule,
+    Biotech,
+    /// Small molecules and biotech drugs.
+    All,
+}
+
+impl DrugType {
+    /// Whether a drug of type `kind`, `small molecule` or `biotech`, is
+    /// selected.
+    pub fn matches(&self, kind: &str) -> bool {
+        match self {
+            DrugType::SmallMolecule => kind == "small molecule",
+            DrugType::Biotech => kind == "biotech",
+            DrugType::All => true,
+        }
+    }
+}
+
+impl FromStr for DrugType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "small_molecule" | "small-molecule" | "small molecule" => Ok(DrugType::SmallMolecule),
+            "biotech" => Ok(DrugType::Biotech),
+            "all" => Ok(DrugType::All),
+            s => Err(format!(
+                "unknown drug type {}, expected small_molecule, biotech or all",
+                s
+            )),
+        }
+    }
+}
+
+/// Identifiers that would otherwise be turned into numbers when they happen
+/// to be all digits, relative to the drug element.
+const STRING_PATHS: &[&str] = &[
+    "drugbank-id",
+    "cas-number",
+    "unii",
+    "salts/salt/drugbank-id",
+    "salts/salt/cas-number",
+    "salts/salt/unii",
+    "products/product/ndc-id",
+    "products/product/ndc-product-code",
+    "products/product/dpd-id",
+    "products/product/ema-ma-number",
+    "general-references/articles/article/pubmed-id",
+    "atc-codes/atc-code/@code",
+    "ahfs-codes/ahfs-code",
+    "pdb-entries/pdb-entry",
+    "patents/patent/number",
+    "external-identifiers/external-identifier/identifier",
+];
+
+/// How `split` converts the XML of a drug to json.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conversion {
+    /// Prefix of attribute keys. The typed exports expect `@`.
+    pub attribute_prefix: String,
+    /// Key of the text of elements that have attributes. The typed exports
+    /// expect `#text`.
+    pub text_key: String,
+    pub empty_elements: EmptyElements,
+    /// Keep text with leading zeros such as `007` as a string.
+    pub leading_zero_as_string: bool,
+    /// JSON type of the values at these paths, on top of `STRING_PATHS`.
+    /// Paths are relative to the drug element, `cas-number` or
+    /// `drugbank-id/@primary`, or absolute like `/drug/cas-number`.
+    pub types: BTreeMap<String, ValueType>,
+    /// Elements written as arrays even when there is only one, like
+    /// `drug/groups/group` or `*/polypeptide`, see `ArrayPaths`.
+    pub arrays: Vec<String>,
+}
+
+impl Default for Conversion {
+    fn default() -> Self {
+        Self {
+            attribute_prefix: "@".to_string(),
+            text_key: "#text".to_string(),
+            empty_elements: EmptyElements::Object,
+            leading_zero_as_string: false,
+            types: BTreeMap::new(),
+            arrays: vec![],
+        }
+    }
+}
+
+impl Conversion {
+    /// Whether attributes and element text get the keys the typed model
+    /// reads, `@` and `#text`.
+    pub fn typed_keys(&self) -> bool {
+        let defaults = Conversion::default();
+        self.attribute_prefix == defaults.attribute_prefix && self.text_key == defaults.text_key
+    }
+
+    /// The type of every path, absolute, identifiers included.
+    pub fn types(&self) -> BTreeMap<String, ValueType> {
+        STRING_PATHS
+            .iter()
+            .map(|f| (f.to_string(), ValueType::String))
+            .chain(self.types.clone())
+            .map(|(path, kind)| {
+                if path.starts_with('/') {
+                    (path, kind)
+                } else {
+                    (format!("/drug/{}", path), kind)
+                }
+            })
+            .collect()
+    }
+}
+
+/// What an empty element like `<toxicity/>` becomes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyElements {
+    /// `{}`
+    Object,
+    /// `null`
+    Null,
+    /// Left out.
+    Ignore,
+}
+
+impl FromStr for EmptyElements {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "object" => Ok(EmptyElements::Object),
+            "null" => Ok(EmptyElements::Null),
+            "ignore" => Ok(EmptyElements::Ignore),
+            s => Err(format!(
+                "unknown empty element handling {}, expected object, null or ignore",
+                s
+            )),
+        }
+    }
+}
+
+/// JSON type of a converted value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    /// Text as is, `0012345` stays a string.
+    String,
+    /// `true` is true, anything else false.
+    Bool,
+    /// Numbers and booleans where the text looks like one.
+    Infer,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Secrets {
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Whether a MongoDB connection string carries `user:password@`.
+pub fn has_credentials(url: &str) -> bool {
+    let rest = url.split_once("://").map(|f| f.1).unwrap_or(url);
+    let authority = rest.split(['/', '?']).next().unwrap_or_default();
+    authority.contains('@')
+}
+
+impl Settings {
+    /// Loads `path`, or `xml2csv.toml` when there is one, then applies the
+    /// environment and the secrets file.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let mut settings = match path {
+            Some(p) => Self::from_file(p)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(DEFAULT_CONFIG_FILE)?
+            }
+            None => Self::default(),
+        };
+
+        settings.apply_env(|key| std::env::var(key).ok())?;
+        settings.load_secrets()?;
+
+        Ok(settings)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        Self::from_toml(&text).file(path)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let settings: Settings = toml::from_str(text)?;
+        if has_credentials(&settings.mongo.url) {
+            return Err(Error::Config(
+                "mongo.url must not contain credentials, use XML2CSV_MONGO_USERNAME/PASSWORD or a secrets file"
+                    .to_string(),
+            ));
+        }
+        Ok(settings)
+    }
+
+    /// Overrides settings with the `XML2CSV_*` variables `get` returns.
+    pub fn apply_env(&mut self, get: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let var = |name: &str| get(&format!("{}{}", ENV_PREFIX, name));
+        let secs = |name: &str, value: String| {
+            value
+                .parse::<u64>()
+                .map_err(|e| Error::Config(format!("{}{} : {}", ENV_PREFIX, name, e)))
+        };
+
+        if let Some(v) = var("MONGO_URL") {
+            self.mongo.url = v;
+        }
+        if let Some(v) = var("MONGO_DATABASE") {
+            self.mongo.database = v;
+        }
+        if let Some(v) = var("MONGO_CONNECT_TIMEOUT_SECS") {
+            self.mongo.connect_timeout_secs = secs("MONGO_CONNECT_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = var("MONGO_SERVER_SELECTION_TIMEOUT_SECS") {
+            self.mongo.server_selection_timeout_secs =
+                secs("MONGO_SERVER_SELECTION_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = var("MONGO_USERNAME") {
+            self.mongo.username = Some(v);
+        }
+        if let Some(v) = var("MONGO_PASSWORD") {
+            self.mongo.password = Some(v);
+        }
+        if let Some(v) = var("COLLECTION_FILTER") {
+            self.collections.filter = v;
+        }
+        if let Some(v) = var("COLLECTION_DRUGS") {
+            self.collections.drugs = v;
+        }
+        if let Some(v) = var("SECRETS_FILE") {
+            self.secrets_file = Some(v);
+        }
+        if let Some(v) = var("CONVERSION_ATTRIBUTE_PREFIX") {
+            self.conversion.attribute_prefix = v;
+        }
+        if let Some(v) = var("CONVERSION_TEXT_KEY") {
+            self.conversion.text_key = v;
+        }
+        if let Some(v) = var("CONVERSION_EMPTY_ELEMENTS") {
+            self.conversion.empty_elements = v.parse().map_err(|e| {
+                Error::Config(format!("{}CONVERSION_EMPTY_ELEMENTS : {}", ENV_PREFIX, e))
+            })?;
+        }
+        if let Some(v) = var("FILTER_DRUG_TYPE") {
+            self.filter.drug_type = v
+                .parse()
+                .map_err(|e| Error::Config(format!("{}FILTER_DRUG_TYPE : {}", ENV_PREFIX, e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Fills in credentials from the secrets file, the environment wins.
+    fn load_secrets(&mut self) -> Result<()> {
+        let path = match &self.secrets_file {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        let secrets: Secrets = toml::from_str(&text).file(path)?;
+
+        if self.mongo.username.is_none() {
+            self.mongo.username = secrets.username;
+        }
+        if self.mongo.password.is_none() {
+            self.mongo.password = secrets.password;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings() {
+        let mut settings = Settings::from_toml(
+            r#"
+[mongo]
+url = "mongodb://staging:27017"
+database = "drugbank_staging"
+
+[collections]
+filter = "filter_staging"
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.mongo.database, "drugbank_staging");
+        assert_eq!(settings.mongo.connect_timeout_secs, 4);
+        assert_eq!(settings.collections.filter, "filter_staging");
+        assert_eq!(settings.collections.drugs, "drugs");
+
+        let env: std::collections::HashMap<String, String> = [
+            ("XML2CSV_MONGO_DATABASE", "drugbank_prod"),
+            ("XML2CSV_MONGO_CONNECT_TIMEOUT_SECS", "10"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        settings.apply_env(|k| env.get(k).cloned()).unwrap();
+        assert_eq!(settings.mongo.database, "drugbank_prod");
+        assert_eq!(settings.mongo.connect_timeout_secs, 10);
+
+        assert!(settings
+            .apply_env(|k| (k == "XML2CSV_MONGO_CONNECT_TIMEOUT_SECS").then(|| "x".to_string()))
+            .is_err());
+        assert_eq!(
+            Settings::from_file("xml2csv.example.toml").unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn test_secrets() {
+        let secrets = std::env::temp_dir().join(format!("{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &secrets,
+            "username = \"reader\"\npassword = \"from-file\"\n",
+        )
+        .unwrap();
+        let env: std::collections::HashMap<String, String> = [
+            ("XML2CSV_MONGO_PASSWORD", "from-env"),
+            ("XML2CSV_SECRETS_FILE", secrets.to_str().unwrap()),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        // the environment wins over the secrets file
+        let mut settings = Settings::default();
+        settings.apply_env(|k| env.get(k).cloned()).unwrap();
+        settings.load_secrets().unwrap();
+        assert_eq!(settings.mongo.username.as_deref(), Some("reader"));
+        assert_eq!(settings.mongo.password.as_deref(), Some("from-env"));
+
+        // credentials don't belong in the settings file
+        assert!(Settings::from_toml("[mongo]\nurl = \"mongodb://u:p@db:27017\"").is_err());
+        assert!(Settings::from_toml("[mongo]\npassword = \"p\"").is_err());
+        assert!(!has_credentials("mongodb://db:27017/?authSource=a@b"));
+
+        let _ = std::fs::remove_file(secrets);
+    }
+
+    #[test]
+    fn test_conversion() {
+        let settings = Settings::from_toml(
+            r#"
+[conversion]
+empty_elements = "null"
+arrays = ["drug/groups/group", "*/polypeptide"]
+
+[conversion.types]
+"cas-number" = "infer"
+"/drug/products/product/generic" = "bool"
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.conversion.empty_elements, EmptyElements::Null);
+        assert_eq!(settings.conversion.attribute_prefix, "@");
+        assert_eq!(settings.conversion.arrays.len(), 2);
+        let types = settings.conversion.types();
+        assert_eq!(types["/drug/cas-number"], ValueType::Infer);
+        assert_eq!(types["/drug/unii"], ValueType::String);
+        assert_eq!(types["/drug/products/product/generic"], ValueType::Bool);
+        assert!(Settings::from_toml("[conversion]\nempty_elements = \"none\"").is_err());
+    }
+
+    #[test]
+    fn test_drug_type() {
+        let mut settings = Settings::from_toml("[filter]\ndrug_type = \"biotech\"").unwrap();
+        assert_eq!(settings.filter.drug_type, DrugType::Biotech);
+        settings
+            .apply_env(|k| (k == "XML2CSV_FILTER_DRUG_TYPE").then(|| "all".to_string()))
+            .unwrap();
+        assert_eq!(settings.filter.drug_type, DrugType::All);
+        assert!(DrugType::SmallMolecule.matches("small molecule"));
+        assert!(!DrugType::SmallMolecule.matches("biotech"));
+        assert!("protein".parse::<DrugType>().is_err());
+    }
 }
@@ -5,28 +5,99 @@ use log::info;
 use mongodb::{
     bson::{self, doc, Bson, Document},
     error::Error,
-    options::{ClientOptions, FindOneOptions, FindOptions},
-    sync::Client,
+    options::{ClientOptions, Credential, FindOneOptions, FindOptions},
+    sync::{Client, Database},
 };
 use once_cell::sync::OnceCell;
 use serde::de::DeserializeOwned;
 
-static INSTANCE: OnceCell<Arc<Client>> = OnceCell::new();
+use crate::config::MongoSettings;
 
-const TABLE_NAME: &'static str = "drugbank";
-pub const COLLECTION_CID_NOT_FOUND: &'static str = "cid_not_found";
+static INSTANCE: OnceCell<Arc<Client>> = OnceCell::new();
+static DATABASE: OnceCell<String> = OnceCell::new();
+pub const COLLECTION_CID_NOT_FOUND: &str = "cid_not_found";
 
-const KEY_UPDATE_TIME: &'static str = "updateTime";
-const KEY_CREATE_TIME: &'static str = "createTime";
+const KEY_UPDATE_TIME: &str = "updateTime";
+const KEY_CREATE_TIME: &str = "createTime";
 
 #[derive(Clone, Debug)]
 pub struct Db;
 
+/// Outcome of one batch of upserts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchResult {
+    pub inserted: u64,
+    pub modified: u64,
+    pub failed: u64,
+    /// Index in the batch and error message of every refused record.
+    pub errors: Vec<(usize, String)>,
+}
+
+impl BatchResult {
+    /// Reads the counts out of the `update` command reply for `len` records.
+    /// A `writeConcernError` counts every record of the batch as failed, the
+    /// server couldn't confirm any of them.
+    fn from_reply(reply: &Document, len: usize) -> Self {
+        if let Ok(e) = reply.get_document("writeConcernError") {
+            let message = e.get_str("errmsg").unwrap_or_default();
+            log::warn!("db write concern error : {}", message);
+            let mut result = Self::default();
+            for i in 0..len {
+                result.refuse(i, message);
+            }
+            return result;
+        }
+
+        let count = |key: &str| match reply.get(key) {
+            Some(Bson::Int32(n)) => *n as u64,
+            Some(Bson::Int64(n)) => *n as u64,
+            _ => 0,
+        };
+        let size = |key: &str| reply.get_array(key).map(|f| f.len() as u64).unwrap_or(0);
+
+        let mut result = Self {
+            inserted: size("upserted"),
+            modified: count("nModified"),
+            ..Self::default()
+        };
+        for e in reply.get_array("writeErrors").into_iter().flatten() {
+            if let Bson::Document(e) = e {
+                let index = match e.get("index") {
+                    Some(Bson::Int32(n)) => *n as usize,
+                    Some(Bson::Int64(n)) => *n as usize,
+                    _ => 0,
+                };
+                result.refuse(index, e.get_str("errmsg").unwrap_or_default());
+            }
+        }
+        result
+    }
+
+    /// Counts the record at `index` of the batch as failed.
+    pub fn refuse(&mut self, index: usize, message: impl Into<String>) {
+        self.failed += 1;
+        self.errors.push((index, message.into()));
+    }
+
+    /// Adds the counts of `other`, errors are left out as their indexes are
+    /// those of `other`'s batch.
+    pub fn add(&mut self, other: &BatchResult) {
+        self.inserted += other.inserted;
+        self.modified += other.modified;
+        self.failed += other.failed;
+    }
+}
+
 impl Db {
     pub fn get_instance() -> &'static Arc<Client> {
         INSTANCE.get().expect("db need init first")
     }
 
+    /// The configured database, see `MongoSettings::database`.
+    pub fn database() -> Database {
+        Db::get_instance().database(DATABASE.get().expect("db need init first"))
+    }
+
     pub fn find<T>(
         table: &str,
         filter: impl Into<Option<Document>>,
@@ -36,27 +107,26 @@ impl Db {
     where
         T: DeserializeOwned,
     {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection(table);
 
-        let mut cursor = collection.find(filter, options)?;
+        let cursor = collection.find(filter, options)?;
 
         // Iterate over the results of the cursor.
-        while let Some(result) = cursor.next() {
+        for result in cursor {
             match result {
                 Ok(document) => {
                     let result = bson::from_bson::<T>(Bson::Document(document));
                     match result {
                         Ok(app) => call_back(app),
                         Err(err) => {
-                            info!("err = {:?}", err);
+                            log::warn!("skip document, deserialize error : {}", err);
                         }
                     }
                 }
                 Err(e) => {
                     info!("error = {:?}", e);
-                    return Err(e.into());
+                    return Err(e);
                 }
             }
         }
@@ -69,16 +139,14 @@ impl Db {
         filter: impl Into<Option<Document>>,
         options: impl Into<Option<FindOneOptions>>,
     ) -> Result<Option<Document>, Error> {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection(table);
 
         collection.find_one(filter, options)
     }
 
     pub fn insert_many(table: &str, data: Vec<Document>) -> Result<(), Error> {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection(table);
         let date = Bson::DateTime(mongodb::bson::DateTime::now());
         let data2: Vec<Document> = data
@@ -97,44 +165,83 @@ impl Db {
     }
 
     pub fn delete_table(table: &str) -> Result<(), Error> {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection::<Document>(table);
-        let _ = collection.drop(None)?;
+        collection.drop(None)?;
         Ok(())
     }
 
-    pub fn save(table: &str, filter: Document, app: Document) -> Result<(), Error> {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
-        let collection = db.collection(table);
-
-        let mut update_doc = app;
-        let date = Bson::DateTime(mongodb::bson::DateTime::now());
-        update_doc.insert(KEY_UPDATE_TIME, date.clone());
-
-        let result = collection.find_one_and_update(
-            filter.clone(),
-            doc! {"$set": update_doc.clone()},
-            None,
-        )?;
-
-        if !result.is_none() {
-            info!("db update");
-            collection.update_one(filter.clone(), doc! {"$set": update_doc}, None)?;
-        } else {
-            update_doc.insert(KEY_CREATE_TIME, date);
-            let result = collection.insert_one(update_doc, None)?;
-
-            info!("db insert {:?}", result);
+    /// Upserts one document, failing when the server refuses the write.
+    pub fn save(table: &str, filter: Document, app: Document) -> crate::error::Result<()> {
+        let result = Db::save_many(table, vec![(filter, app)], 1)?;
+        info!("db save {:?}", result);
+
+        let failed: u64 = result.iter().map(|f| f.failed).sum();
+        if failed > 0 {
+            return Err(crate::error::Error::Write {
+                table: table.to_string(),
+                batch: 0,
+                failed,
+            });
         }
 
         Ok(())
     }
 
+    /// Upserts `(filter, document)` pairs with one `update` command per
+    /// `batch_size` records. Existing documents get their fields `$set`, new
+    /// ones also get `createTime` through `$setOnInsert`.
+    ///
+    /// Batches are unordered, a failing record doesn't stop the rest of its
+    /// batch and is counted in `failed` instead. A command that fails
+    /// altogether stops the save with `Error::PartialSave`, holding the
+    /// counts of the batches written before it.
+    pub fn save_many(
+        table: &str,
+        records: Vec<(Document, Document)>,
+        batch_size: usize,
+    ) -> crate::error::Result<Vec<BatchResult>> {
+        let db = Db::database();
+
+        let mut results = vec![];
+        for batch in records.chunks(batch_size.max(1)) {
+            let date = Bson::DateTime(mongodb::bson::DateTime::now());
+            let updates: Vec<Document> = batch
+                .iter()
+                .map(|(filter, app)| {
+                    let mut update_doc = app.clone();
+                    update_doc.insert(KEY_UPDATE_TIME, date.clone());
+                    doc! {
+                        "q": filter.clone(),
+                        "u": {"$set": update_doc, "$setOnInsert": {KEY_CREATE_TIME: date.clone()}},
+                        "upsert": true,
+                    }
+                })
+                .collect();
+
+            let reply = match db.run_command(
+                doc! {"update": table, "updates": updates, "ordered": false},
+                None,
+            ) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    return Err(crate::error::Error::PartialSave {
+                        saved: results,
+                        source: Box::new(e.into()),
+                    })
+                }
+            };
+
+            let result = BatchResult::from_reply(&reply, batch.len());
+            info!("db save batch {:?}", result);
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
     pub fn delete(table: &str, filter: Document) -> Result<(), Error> {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection::<Document>(table);
 
         let result = collection.delete_one(filter, None)?;
@@ -144,9 +251,19 @@ impl Db {
         Ok(())
     }
 
+    pub fn delete_many(table: &str, filter: Document) -> Result<u64, Error> {
+        let db = Db::database();
+        let collection = db.collection::<Document>(table);
+
+        let result = collection.delete_many(filter, None)?;
+
+        info!("db delete {:?}", result);
+
+        Ok(result.deleted_count)
+    }
+
     pub fn contians(table: &str, filter: Document) -> bool {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection::<Document>(table);
 
         let result = collection.count_documents(filter, None);
@@ -158,29 +275,85 @@ impl Db {
     }
 
     pub fn count(table: &str, filter: Document) -> u64 {
-        let client = Db::get_instance();
-        let db = client.database(TABLE_NAME);
+        let db = Db::database();
         let collection = db.collection::<Document>(table);
 
         let result = collection.count_documents(filter, None);
 
-        match result {
-            Ok(d) => d,
-            Err(_) => 0,
-        }
+        result.unwrap_or_default()
     }
 }
 
-pub fn init_db(url: &str) {
+pub fn init_db(settings: &MongoSettings) -> Result<(), crate::error::Error> {
     if INSTANCE.get().is_some() {
-        return;
+        return Ok(());
     }
-    let mut client_options = ClientOptions::parse(url).unwrap();
-    client_options.connect_timeout = Some(Duration::new(4, 0));
+    let mut client_options = ClientOptions::parse(&settings.url)?;
+    client_options.connect_timeout = Some(Duration::from_secs(settings.connect_timeout_secs));
     // 选择超时
-    client_options.server_selection_timeout = Some(Duration::new(8, 0));
+    client_options.server_selection_timeout =
+        Some(Duration::from_secs(settings.server_selection_timeout_secs));
+    if settings.username.is_some() || settings.password.is_some() {
+        client_options.credential = Some(
+            Credential::builder()
+                .username(settings.username.clone())
+                .password(settings.password.clone())
+                .build(),
+        );
+    }
+
+    let client = Client::with_options(client_options)?;
+    let _ = DATABASE.set(settings.database.clone());
+    let _ = INSTANCE.set(Arc::new(client));
 
-    INSTANCE
-        .set(Arc::new(Client::with_options(client_options).unwrap()))
-        .expect("db init error");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_result() {
+        let reply = doc! {
+            "n": 3,
+            "nModified": 1,
+            "upserted": [{"index": 0, "_id": 1}, {"index": 2, "_id": 2}],
+            "writeErrors": [{"index": 3, "code": 11000, "errmsg": "duplicate key"}],
+            "ok": 1.0,
+        };
+        assert_eq!(
+            BatchResult::from_reply(&reply, 4),
+            BatchResult {
+                inserted: 2,
+                modified: 1,
+                failed: 1,
+                errors: vec![(3, "duplicate key".to_string())],
+            }
+        );
+        assert_eq!(
+            BatchResult::from_reply(&doc! {"n": 0, "ok": 1.0}, 0),
+            BatchResult::default()
+        );
+
+        let reply = doc! {
+            "n": 2,
+            "nModified": 0,
+            "upserted": [{"index": 0, "_id": 1}, {"index": 1, "_id": 2}],
+            "writeConcernError": {"code": 64, "errmsg": "waiting for replication timed out"},
+            "ok": 1.0,
+        };
+        assert_eq!(
+            BatchResult::from_reply(&reply, 2),
+            BatchResult {
+                inserted: 0,
+                modified: 0,
+                failed: 2,
+                errors: vec![
+                    (0, "waiting for replication timed out".to_string()),
+                    (1, "waiting for replication timed out".to_string()),
+                ],
+            }
+        );
+    }
 }
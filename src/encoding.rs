@@ -0,0 +1,260 @@
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, BufReader, Cursor, Read},
+};
+
+use encoding_rs::{Decoder, DecoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::error::{Error, Result};
+
+/// Bytes looked at to find the encoding.
+const SNIFF_LEN: usize = 1024;
+
+/// Bytes transcoded at a time.
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// Works out the encoding of a document from its first bytes: a byte order
+/// mark, a UTF-16 `<?` without one, or the `encoding` of the XML declaration.
+/// Falls back to UTF-8. Returns the encoding and the length of the BOM.
+pub fn detect(bytes: &[u8]) -> (&'static Encoding, usize) {
+    if let Some(bom) = Encoding::for_bom(bytes) {
+        return bom;
+    }
+
+    match bytes {
+        [b'<', 0, b'?', 0, ..] | [b'{', 0, ..] => return (UTF_16LE, 0),
+        [0, b'<', 0, b'?', ..] | [0, b'{', ..] => return (UTF_16BE, 0),
+        _ => {}
+    }
+
+    match declared(&bytes[..bytes.len().min(SNIFF_LEN)]) {
+        // an ASCII readable declaration can't be UTF-16
+        Some(e) if e != UTF_16LE && e != UTF_16BE => (e, 0),
+        _ => (UTF_8, 0),
+    }
+}
+
+/// The encoding named by `<?xml ... encoding="..."?>`.
+fn declared(bytes: &[u8]) -> Option<&'static Encoding> {
+    let start = bytes.iter().position(|f| !f.is_ascii_whitespace())?;
+    let bytes = &bytes[start..];
+    if !bytes.starts_with(b"<?xml") {
+        return None;
+    }
+    let end = bytes.windows(2).position(|f| f == b"?>")?;
+    let decl = std::str::from_utf8(&bytes[..end]).ok()?;
+
+    let rest = &decl[decl.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|f| *f == '"' || *f == '\'')?;
+    let label = &rest[1..];
+    let label = &label[..label.find(quote)?];
+
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Decodes a whole document to UTF-8, failing on malformed input instead of
+/// replacing it.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    let (encoding, bom) = detect(bytes);
+    let bytes = &bytes[bom..];
+
+    if encoding == UTF_8 {
+        return std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Error::Decode {
+                encoding: encoding.name().to_string(),
+                message: e.to_string(),
+            });
+    }
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or_else(|| Error::Decode {
+            encoding: encoding.name().to_string(),
+            message: "malformed byte sequence".to_string(),
+        })
+}
+
+/// Wraps a reader so it yields UTF-8 whatever the source encoding is.
+///
+/// UTF-8 passes through untouched apart from the BOM, so invalid bytes are
+/// still reported by the XML reader. Other encodings are transcoded on the
+/// fly, where a malformed sequence is an `InvalidData` read error.
+pub fn utf8_reader<R: Read + 'static>(mut reader: R, name: &str) -> Result<Box<dyn BufRead>> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    (&mut reader)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .map_err(Error::io(name))?;
+
+    let (encoding, bom) = detect(&head);
+    head.drain(..bom);
+    if encoding == UTF_8 {
+        return Ok(Box::new(BufReader::new(Cursor::new(head).chain(reader))));
+    }
+
+    log::info!("transcoding {} from {} to UTF-8", name, encoding.name());
+    Ok(Box::new(BufReader::new(Transcoder::new(
+        Cursor::new(head).chain(reader),
+        encoding,
+    ))))
+}
+
+/// Transcodes to UTF-8 while reading, without replacing malformed input.
+struct Transcoder<R> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    /// Read position in `output`.
+    pos: usize,
+    /// Source bytes decoded so far.
+    offset: usize,
+    finished: bool,
+}
+
+impl<R: Read> Transcoder<R> {
+    fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            decoder: encoding.new_decoder_without_bom_handling(),
+            input: vec![0; CHUNK_LEN],
+            output: vec![],
+            pos: 0,
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Decodes the next chunk of the source into `output`.
+    fn fill(&mut self) -> io::Result<()> {
+        let len = self.inner.read(&mut self.input)?;
+        let last = len == 0;
+
+        // sized for the whole chunk, so the decoder never runs out of room
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length_without_replacement(len)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "chunk too long"))?;
+        self.output.resize(capacity, 0);
+        let (result, read, written) = self.decoder.decode_to_utf8_without_replacement(
+            &self.input[..len],
+            &mut self.output,
+            last,
+        );
+        self.output.truncate(written);
+        self.pos = 0;
+        self.offset += read;
+        self.finished = last;
+
+        match result {
+            DecoderResult::InputEmpty => Ok(()),
+            DecoderResult::Malformed(..) | DecoderResult::OutputFull => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "malformed {} byte sequence before byte {}",
+                    self.decoder.encoding().name(),
+                    self.offset
+                ),
+            )),
+        }
+    }
+}
+
+impl<R: Read> Read for Transcoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.output.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let len = buf.len().min(self.output.len() - self.pos);
+        buf[..len].copy_from_slice(&self.output[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xFF, 0xFE] } else { vec![] };
+        bytes.extend(s.encode_utf16().flat_map(|f| f.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"\xEF\xBB\xBF<a/>"), (UTF_8, 3));
+        assert_eq!(detect(&utf16le("<a/>", true)), (UTF_16LE, 2));
+        assert_eq!(detect(&utf16le("<?xml?>", false)).0, UTF_16LE);
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\" encoding='ISO-8859-1'?><a/>")
+                .0
+                .name(),
+            "windows-1252"
+        );
+        assert_eq!(
+            detect(b"  <?xml version=\"1.0\" encoding=\"UTF-16\"?><a/>").0,
+            UTF_8
+        );
+        assert_eq!(detect(b"{\"name\": 1}").0, UTF_8);
+    }
+
+    #[test]
+    fn test_decode() {
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"latin1\"?><name>Ca\xE9</name>";
+        assert!(decode(latin1).unwrap().ends_with("<name>Ca\u{e9}</name>"));
+
+        let json = utf16le("{\"name\": \"Ca\u{e9}\"}", true);
+        assert_eq!(decode(&json).unwrap(), "{\"name\": \"Ca\u{e9}\"}");
+
+        let e = decode(b"{\"name\": \"Ca\xE9\"}").unwrap_err();
+        assert!(matches!(e, Error::Decode { .. }));
+        assert!(e.to_string().starts_with("UTF-8 decode error"));
+
+        // lone surrogate
+        assert!(decode(&[0xFF, 0xFE, 0x00, 0xD8, b'a', 0]).is_err());
+    }
+
+    #[test]
+    fn test_utf8_reader() {
+        let read = |bytes: Vec<u8>| {
+            let mut s = String::new();
+            utf8_reader(Cursor::new(bytes), "test")
+                .unwrap()
+                .read_to_string(&mut s)
+                .unwrap();
+            s
+        };
+
+        assert_eq!(read(b"\xEF\xBB\xBF<a/>".to_vec()), "<a/>");
+        assert_eq!(read(utf16le("<a>\u{e9}</a>", true)), "<a>\u{e9}</a>");
+        assert_eq!(
+            read(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>\xE9</a>".to_vec()),
+            "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>\u{e9}</a>"
+        );
+
+        // longer than a chunk, characters split across chunks survive
+        let long = "\u{e9}".repeat(CHUNK_LEN);
+        assert_eq!(read(utf16le(&long, true)), long);
+
+        // a lone surrogate fails the read instead of becoming U+FFFD
+        let mut s = String::new();
+        let e = utf8_reader(
+            Cursor::new(vec![0xFF, 0xFE, b'a', 0, 0x00, 0xD8, b'b', 0]),
+            "test",
+        )
+        .unwrap()
+        .read_to_string(&mut s)
+        .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().starts_with("malformed UTF-16LE"), "{}", e);
+    }
+}
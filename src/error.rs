@@ -0,0 +1,201 @@
+use std::{fmt, path::Path};
+
+use mongodb::bson;
+use thiserror::Error;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Where in the input an error happened, as far as it is known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub file: Option<String>,
+    pub drugbank_id: Option<String>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, &self.drugbank_id) {
+            (Some(file), Some(id)) => write!(f, "{} ({})", file, id),
+            (Some(file), None) => write!(f, "{}", file),
+            (None, Some(id)) => write!(f, "{}", id),
+            (None, None) => write!(f, "unknown location"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path} : {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("xml error at position {position} : {message}")]
+    Xml { position: usize, message: String },
+    #[error("{encoding} decode error : {message}")]
+    Decode { encoding: String, message: String },
+    #[error("xml to json error : {0}")]
+    Convert(String),
+    /// A value of the converted drug json doesn't have the expected shape,
+    /// `path` is its JSON path.
+    #[error("unexpected value at {path} : {message}")]
+    Value { path: String, message: String },
+    #[error("json error : {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("yaml error : {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("toml error : {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("bson error : {0}")]
+    BsonSer(#[from] bson::ser::Error),
+    #[error("bson error : {0}")]
+    BsonDe(#[from] bson::de::Error),
+    #[error("bson error : {0}")]
+    ExtJson(#[from] bson::extjson::de::Error),
+    #[error("mongodb error : {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("sqlite error : {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("csv error : {0}")]
+    Csv(#[from] csv::Error),
+    #[error("arrow error : {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[error("parquet error : {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// Output schema and row don't fit together.
+    #[error("{0}")]
+    Schema(String),
+    #[error("config error : {0}")]
+    Config(String),
+    /// A `--where` expression that can't be parsed.
+    #[error("where expression error at column {column} : {message}")]
+    Predicate { column: usize, message: String },
+    /// Records the database refused within one batch of upserts.
+    #[error("{failed} writes of batch {batch} to {table} failed")]
+    Write {
+        table: String,
+        batch: usize,
+        failed: u64,
+    },
+    /// A batched save that stopped partway, `saved` holds the counts of the
+    /// batches written before `source` stopped it.
+    #[error("save stopped after {} batches : {source}", saved.len())]
+    PartialSave {
+        saved: Vec<crate::db::BatchResult>,
+        source: Box<Error>,
+    },
+    /// One record the database refused.
+    #[error("write to {table} refused : {message}")]
+    Refused { table: String, message: String },
+    #[error("{failed} records failed, more than the allowed {max}")]
+    TooManyFailures { failed: usize, max: usize },
+    #[error("{location} : {source}")]
+    Context {
+        location: Location,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Wraps an io error with the path it happened on, for `map_err`.
+    pub fn io<P: AsRef<Path>>(path: P) -> impl FnOnce(std::io::Error) -> Error {
+        let path = path.as_ref().display().to_string();
+        move |source| Error::Io { path, source }
+    }
+
+    pub fn value(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Error::Value {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    fn locate(self, update: impl FnOnce(&mut Location)) -> Self {
+        match self {
+            Error::Context {
+                mut location,
+                source,
+            } => {
+                update(&mut location);
+                Error::Context { location, source }
+            }
+            e => {
+                let mut location = Location::default();
+                update(&mut location);
+                Error::Context {
+                    location,
+                    source: Box::new(e),
+                }
+            }
+        }
+    }
+
+    /// Records the input file, unless a more specific one is known already.
+    pub fn with_file(self, file: &str) -> Self {
+        self.locate(|l| {
+            l.file.get_or_insert_with(|| file.to_string());
+        })
+    }
+
+    pub fn with_drug(self, drugbank_id: &str) -> Self {
+        self.locate(|l| {
+            l.drugbank_id.get_or_insert_with(|| drugbank_id.to_string());
+        })
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Context { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+}
+
+/// Adds location context to the error of a `Result`.
+pub trait ResultExt<T> {
+    fn file(self, file: &str) -> Result<T>;
+    fn drug(self, drugbank_id: &str) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn file(self, file: &str) -> Result<T> {
+        self.map_err(|e| e.into().with_file(file))
+    }
+
+    fn drug(self, drugbank_id: &str) -> Result<T> {
+        self.map_err(|e| e.into().with_drug(drugbank_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let e: Result<()> = Err(Error::value(
+            "calculated-properties.property[0].value",
+            "expected text",
+        ));
+        let e = e.drug("DB00001").file("data/json/0.json").unwrap_err();
+
+        assert_eq!(
+            e.location(),
+            Some(&Location {
+                file: Some("data/json/0.json".to_string()),
+                drugbank_id: Some("DB00001".to_string()),
+            })
+        );
+        assert_eq!(
+            e.to_string(),
+            "data/json/0.json (DB00001) : unexpected value at calculated-properties.property[0].value : expected text"
+        );
+
+        // the innermost file wins
+        let e = e.with_file("other.json");
+        assert_eq!(
+            e.location().unwrap().file.as_deref(),
+            Some("data/json/0.json")
+        );
+    }
+}
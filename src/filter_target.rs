@@ -1,165 +1,389 @@
-use std::collections::HashMap;
-use std::{fs::File, io::BufReader, path::Path};
-
-use csv::{QuoteStyle, WriterBuilder};
-use serde::{Deserialize, Deserializer, Serialize};
-use serde_json::Value;
-use serde_with::formats::PreferMany;
-use serde_with::serde_as;
-
-#[derive(Serialize, Deserialize)]
-pub struct Target {
-    #[serde(default)]
-    pub polypeptide: Option<PolypeptideOrVec>,
-}
+use std::path::Path;
 
-#[derive(Serialize, Debug, Deserialize)]
-#[serde(untagged)]
-pub enum PolypeptideOrVec {
-    None,
-    One(Polypeptide),
-    Many(Vec<Polypeptide>),
-}
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Polypeptide {
-    #[serde(rename = "gene-name")]
-    pub gene_name: Value,
-}
+use crate::{
+    error::Result,
+    model::Drug,
+    predicate::Predicate,
+    quarantine::Quarantine,
+    source::typed_records,
+    table::{save_to_file, Cell, Field, Kind, Tabular},
+};
 
-#[serde_as]
-#[derive(Serialize, Deserialize)]
-pub struct Targets {
-    #[serde_as(as = "serde_with::OneOrMany<_, PreferMany>")]
-    pub target: Vec<Target>,
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DrugCsv {
+    name: String,
+    gene: String,
+    toxicity: String,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Drug {
-    pub name: String,
-    pub toxicity: Value,
-    #[serde(deserialize_with = "target_or_object")]
-    pub targets: Targets,
+/// One row per drug–target–polypeptide.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DrugTargetCsv {
+    pub drugbank_id: String,
+    pub drug_name: String,
+    pub target_id: String,
+    pub target_name: String,
+    pub organism: String,
+    pub actions: Vec<String>,
+    pub known_action: String,
+    pub uniprot_id: String,
+    pub polypeptide_name: String,
+    pub polypeptide_source: String,
+    pub gene_name: String,
 }
 
-fn target_or_object<'de, D>(deserializer: D) -> Result<Targets, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum TargetOrObject {
-        T(Targets),
-        N(HashMap<String, String>),
+impl Tabular for DrugCsv {
+    fn fields() -> Vec<Field> {
+        ["name", "gene", "toxicity"]
+            .iter()
+            .map(|f| Field::new(f, Kind::Utf8))
+            .collect()
     }
 
-    Ok(match TargetOrObject::deserialize(deserializer)? {
-        TargetOrObject::T(v) => v, // Ignoring parsing errors
-        TargetOrObject::N(_) => Targets { target: vec![] },
-    })
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DrugCsv {
-    name: String,
-    gene: String,
-    toxicity: String,
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::text(&self.name),
+            Cell::text(&self.gene),
+            Cell::text(&self.toxicity),
+        ]
+    }
 }
 
-fn save_to_file<T: Serialize>(
-    name: &str,
-    v: &Vec<T>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mut writer = WriterBuilder::new()
-        .quote_style(QuoteStyle::Necessary)
-        .from_path(name)?;
+impl Tabular for DrugTargetCsv {
+    fn fields() -> Vec<Field> {
+        [
+            "drugbank_id",
+            "drug_name",
+            "target_id",
+            "target_name",
+            "organism",
+            "actions",
+            "known_action",
+            "uniprot_id",
+            "polypeptide_name",
+            "polypeptide_source",
+            "gene_name",
+        ]
+        .iter()
+        .map(|f| match *f {
+            "actions" => Field::new(f, Kind::List),
+            f => Field::new(f, Kind::Utf8),
+        })
+        .collect()
+    }
 
-    for person in v {
-        writer.serialize(person)?;
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::text(&self.drugbank_id),
+            Cell::text(&self.drug_name),
+            Cell::text(&self.target_id),
+            Cell::text(&self.target_name),
+            Cell::text(&self.organism),
+            Cell::List(self.actions.clone()),
+            Cell::text(&self.known_action),
+            Cell::text(&self.uniprot_id),
+            Cell::text(&self.polypeptide_name),
+            Cell::text(&self.polypeptide_source),
+            Cell::text(&self.gene_name),
+        ]
     }
+}
 
-    writer.flush()?;
+pub fn read_json<P: AsRef<Path>>(path: P) -> Result<DrugCsv> {
+    let drug: Drug = crate::filter::read_drug(&path.as_ref().to_string_lossy())?;
 
-    Ok(())
+    Ok(drug_csv(&drug))
 }
 
-pub fn read_json<P: AsRef<Path>>(path: P) -> DrugCsv {
-    let file = File::open(path).unwrap();
-    let reader = BufReader::new(file);
-    let drug: Drug = serde_json::from_reader(reader).unwrap();
-
-    let vv: Vec<Target> = drug.targets.target.into();
-
-    let genes = vv
-        .into_iter()
-        .filter_map(|f| match f.polypeptide {
-            Some(p) => match p {
-                PolypeptideOrVec::None => None,
-                PolypeptideOrVec::One(pp) => match pp.gene_name.clone() {
-                    Value::String(s) => Some(s),
-                    _ => None,
-                },
-                PolypeptideOrVec::Many(m) => {
-                    let gg = m
-                        .iter()
-                        .filter_map(|p| match p.gene_name.clone() {
-                            Value::String(s) => Some(s),
-                            _ => None,
-                        })
-                        .collect::<Vec<String>>()
-                        .join("|");
-
-                    Some(gg)
-                }
-            },
-            None => None,
-        })
-        .collect::<Vec<String>>()
+fn drug_csv(drug: &Drug) -> DrugCsv {
+    let genes = drug
+        .targets
+        .target
+        .iter()
+        .flat_map(|t| &t.polypeptide)
+        .map(|p| p.gene_name.as_str())
+        .filter(|f| !f.is_empty())
+        .collect::<Vec<&str>>()
         .join("|");
 
     DrugCsv {
         name: drug.name.clone(),
         gene: genes,
-        toxicity: match drug.toxicity.clone() {
-            Value::String(e) => e.replace("\r\n", "").replace("\n", ""),
-            _ => "".to_string(),
-        },
+        toxicity: drug.toxicity.replace("\r\n", "").replace('\n', ""),
     }
 }
 
+/// Writes the name/gene/toxicity table for every drug of `input`, a split
+/// directory or an NDJSON stream, that matches `predicate`, as Parquet when
+/// `output` ends in `.parquet` and CSV otherwise. Drugs that can't be read
+/// are quarantined and skipped.
+///
+/// Drugs are read in parallel, rows are sorted by drugbank id.
+pub fn targets_csv(
+    input: &str,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let drugs = typed_records(input)?
+        .par_bridge()
+        .map(|record| {
+            let record = record?;
+            log::debug!("path = {:?}", record.name());
+            Ok(record
+                .read::<Drug>(quarantine)
+                .filter(|drug| predicate.is_none_or(|p| p.matches(drug)))
+                .map(|drug| (drug.primary_id().to_string(), drug_csv(&drug))))
+        })
+        .collect::<Result<Vec<Option<(String, DrugCsv)>>>>()?;
+
+    let mut drugs: Vec<(String, DrugCsv)> = drugs.into_iter().flatten().collect();
+    drugs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let drugs: Vec<DrugCsv> = drugs.into_iter().map(|f| f.1).collect();
+    save_to_file(output, &drugs)?;
+
+    Ok(drugs.len())
+}
+
+/// One row per target polypeptide of `drug`, a target without polypeptides
+/// gets a single row with the polypeptide columns empty.
+pub fn drug_targets(drug: &Drug) -> Vec<DrugTargetCsv> {
+    let drugbank_id = drug.primary_id();
+
+    drug.targets
+        .target
+        .iter()
+        .flat_map(|t| {
+            let row = DrugTargetCsv {
+                drugbank_id: drugbank_id.to_string(),
+                drug_name: drug.name.clone(),
+                target_id: t.id.clone(),
+                target_name: t.name.clone(),
+                organism: t.organism.clone(),
+                actions: t.actions.action.clone(),
+                known_action: t.known_action.clone(),
+                uniprot_id: String::new(),
+                polypeptide_name: String::new(),
+                polypeptide_source: String::new(),
+                gene_name: String::new(),
+            };
+            if t.polypeptide.is_empty() {
+                return vec![row];
+            }
+
+            t.polypeptide
+                .iter()
+                .map(|p| DrugTargetCsv {
+                    uniprot_id: p.id.clone(),
+                    polypeptide_name: p.name.clone(),
+                    polypeptide_source: p.source.clone(),
+                    gene_name: p.gene_name.clone(),
+                    ..row.clone()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes the full drug–target table for every drug of `input`, a split
+/// directory or an NDJSON stream, that matches `predicate`, as Parquet when
+/// `output` ends in `.parquet` and CSV otherwise. Drugs that can't be read
+/// are quarantined and skipped.
+///
+/// Drugs are read in parallel, rows are sorted by drugbank id and keep the
+/// target order within a drug.
+pub fn drug_targets_csv(
+    input: &str,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let drugs = typed_records(input)?
+        .par_bridge()
+        .map(|record| {
+            Ok(record?
+                .read::<Drug>(quarantine)
+                .filter(|drug| predicate.is_none_or(|p| p.matches(drug)))
+                .map(|drug| drug_targets(&drug)))
+        })
+        .collect::<Result<Vec<Option<Vec<DrugTargetCsv>>>>>()?;
+
+    let mut rows: Vec<DrugTargetCsv> = drugs.into_iter().flatten().flatten().collect();
+    rows.sort_by(|a, b| a.drugbank_id.cmp(&b.drugbank_id));
+
+    save_to_file(output, &rows)?;
+
+    Ok(rows.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn check(id: usize) {
-        let path = format!("data/json/{}.json", id);
-        log::info!("start parse {}", &path);
-        log::info!("DrugCsv: {:?}", serde_json::to_string(&read_json(&path)));
+    /// Splits the sample dump into a fresh temp directory.
+    fn split_sample() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            dir.join("json").to_str().unwrap(),
+            0,
+            &Default::default(),
+            &Quarantine::default(),
+        )
+        .unwrap();
+        dir
+    }
+
+    fn check(dir: &Path, id: &str) -> DrugCsv {
+        let json = dir.join("json");
+        let path = crate::manifest::Manifest::load(&json)
+            .unwrap()
+            .path(&json, id)
+            .unwrap();
+        log::info!("start parse {:?}", &path);
+        let drug = read_json(&path).unwrap();
+        log::info!("DrugCsv: {:?}", serde_json::to_string(&drug));
+        drug
+    }
+
+    #[test]
+    fn test_drug_targets() {
+        let drugs = crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .map(|f| serde_json::from_value::<Drug>(f.unwrap()).unwrap())
+            .map(|f| drug_targets(&f))
+            .collect::<Vec<_>>();
+
+        assert_eq!(drugs[0].len(), 1);
+        assert_eq!(drugs[1].len(), 3);
+        assert!(drugs[2].is_empty());
+
+        assert_eq!(
+            drugs[1][0],
+            DrugTargetCsv {
+                drugbank_id: "DB00006".to_string(),
+                drug_name: "Bivalirudin".to_string(),
+                target_id: "BE0000048".to_string(),
+                target_name: "Prothrombin".to_string(),
+                organism: "Humans".to_string(),
+                actions: vec!["inhibitor".to_string(), "antagonist".to_string()],
+                known_action: "yes".to_string(),
+                uniprot_id: "P00734".to_string(),
+                polypeptide_name: "Prothrombin".to_string(),
+                polypeptide_source: "Swiss-Prot".to_string(),
+                gene_name: "F2".to_string(),
+            }
+        );
+        assert_eq!(drugs[1][2].uniprot_id, "P05106");
+        assert!(drugs[1][2].actions.is_empty());
+        assert_eq!(drugs[1][2].known_action, "unknown");
+    }
+
+    #[test]
+    fn test_csv_sorted() {
+        let dir = split_sample();
+        let json = dir.join("json");
+        let json = json.to_str().unwrap();
+
+        let csv = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let quarantine = Quarantine::default();
+        assert_eq!(
+            targets_csv(json, &csv("a.csv"), None, &quarantine).unwrap(),
+            3
+        );
+        targets_csv(json, &csv("b.csv"), None, &quarantine).unwrap();
+        let a = std::fs::read_to_string(csv("a.csv")).unwrap();
+        assert_eq!(a, std::fs::read_to_string(csv("b.csv")).unwrap());
+        // DB00001, DB00002, DB00006
+        let names: Vec<_> = a.lines().skip(1).map(|f| f.split(',').next()).collect();
+        assert_eq!(
+            names,
+            vec![Some("Lepirudin"), Some("Withdrawnol"), Some("Bivalirudin")]
+        );
+
+        assert_eq!(
+            drug_targets_csv(json, &csv("c.csv"), None, &quarantine).unwrap(),
+            4
+        );
+        let ids: Vec<_> = std::fs::read_to_string(csv("c.csv"))
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|f| f[..7].to_string())
+            .collect();
+        assert_eq!(ids, vec!["DB00001", "DB00006", "DB00006", "DB00006"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_empty_elements_ignored() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let json = dir.join("json");
+        let json = json.to_str().unwrap();
+        let conversion = crate::config::Conversion {
+            empty_elements: crate::config::EmptyElements::Ignore,
+            ..Default::default()
+        };
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            json,
+            0,
+            &conversion,
+            &Quarantine::default(),
+        )
+        .unwrap();
+
+        // <toxicity/> and <targets/> are left out, which is no error
+        let quarantine = Quarantine::default();
+        let csv = dir.join("targets.csv");
+        assert_eq!(
+            targets_csv(json, csv.to_str().unwrap(), None, &quarantine).unwrap(),
+            3
+        );
+        assert_eq!(
+            drug_targets_csv(json, csv.to_str().unwrap(), None, &quarantine).unwrap(),
+            4
+        );
+        assert!(quarantine.failures().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
     }
 
     #[test]
     fn test_read_file() {
         crate::config::init_config();
-        check(0);
-        check(1);
-        check(4080);
-        check(14203);
-        check(1273);
-        check(11710);
+        let dir = split_sample();
+
+        assert_eq!(check(&dir, "DB00001").gene, "F2");
+        assert_eq!(check(&dir, "DB00002").name, "Withdrawnol");
+        assert_eq!(check(&dir, "DB00006").name, "Bivalirudin");
+
+        let _ = std::fs::remove_dir_all(dir);
     }
 
     #[test]
     fn test_name() {
         crate::config::init_config();
-        let drugs: Vec<DrugCsv> = std::fs::read_dir("data/json")
-            .unwrap()
-            .map(|entry| {
-                let path = entry.unwrap().path();
-                log::info!("path = {:?}", &path);
-                read_json(&path)
-            })
-            .collect();
+        let dir = split_sample();
+        let drugs: Vec<DrugCsv> =
+            crate::filter::find_json_files(dir.join("json").to_str().unwrap())
+                .unwrap()
+                .iter()
+                .map(|path| {
+                    log::info!("path = {:?}", &path);
+                    read_json(path)
+                })
+                .filter_map(|f| f.ok())
+                .collect();
+        assert_eq!(drugs.len(), 3);
+
+        save_to_file(dir.join("target_gene.csv").to_str().unwrap(), &drugs).unwrap();
 
-        let _ = save_to_file("data/target_gene.csv", &drugs);
+        let _ = std::fs::remove_dir_all(dir);
     }
 }
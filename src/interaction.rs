@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    model::Drug,
+    predicate::Predicate,
+    quarantine::Quarantine,
+    table::{save_to_file, Cell, Field, Kind, Tabular},
+};
+
+/// Undirected drug–drug interaction, `drugbank_id_a` sorts before `drugbank_id_b`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interaction {
+    pub drugbank_id_a: String,
+    pub name_a: String,
+    pub drugbank_id_b: String,
+    pub name_b: String,
+    pub description: String,
+    /// Both ends are drugs of the same release.
+    pub in_release: bool,
+}
+
+impl Tabular for Interaction {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("drugbank_id_a", Kind::Utf8),
+            Field::new("name_a", Kind::Utf8),
+            Field::new("drugbank_id_b", Kind::Utf8),
+            Field::new("name_b", Kind::Utf8),
+            Field::new("description", Kind::Utf8),
+            Field::new("in_release", Kind::Boolean),
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::text(&self.drugbank_id_a),
+            Cell::text(&self.name_a),
+            Cell::text(&self.drugbank_id_b),
+            Cell::text(&self.name_b),
+            Cell::text(&self.description),
+            Cell::Bool(self.in_release),
+        ]
+    }
+}
+
+/// Deduplicated edge list built from the `drug-interactions` of every drug.
+#[derive(Debug, Default)]
+pub struct InteractionGraph {
+    ids: HashSet<String>,
+    edges: BTreeMap<(String, String), Interaction>,
+}
+
+impl InteractionGraph {
+    /// Counts `drug`, by its primary id and aliases, as part of the release
+    /// without adding its edges.
+    pub fn release(&mut self, drug: &Drug) {
+        self.ids.insert(drug.primary_id().to_string());
+        self.ids.extend(drug.aliases());
+    }
+
+    /// `release` that also adds the edges of `drug`.
+    pub fn add(&mut self, drug: &Drug) {
+        self.release(drug);
+        let id = drug.primary_id().to_string();
+
+        for f in &drug.drug_interactions.drug_interaction {
+            let partner = &f.drugbank_id.text;
+            if partner.is_empty() || *partner == id {
+                continue;
+            }
+            let partner_name = &f.name;
+
+            let (a, b) = if id < *partner {
+                ((&id, &drug.name), (partner, partner_name))
+            } else {
+                ((partner, partner_name), (&id, &drug.name))
+            };
+
+            // the same pair is listed under both drugs, keep the first one
+            self.edges
+                .entry((a.0.clone(), b.0.clone()))
+                .or_insert_with(|| Interaction {
+                    drugbank_id_a: a.0.clone(),
+                    name_a: a.1.clone(),
+                    drugbank_id_b: b.0.clone(),
+                    name_b: b.1.clone(),
+                    description: f.description.clone(),
+                    in_release: false,
+                });
+        }
+    }
+
+    /// Sorted edges, flagging those whose partner isn't part of the release.
+    pub fn edges(self) -> Vec<Interaction> {
+        let ids = self.ids;
+        self.edges
+            .into_values()
+            .map(|mut f| {
+                f.in_release = ids.contains(&f.drugbank_id_a) && ids.contains(&f.drugbank_id_b);
+                f
+            })
+            .collect()
+    }
+}
+
+/// Writes the interaction edge list of every drug of `input`, a split
+/// directory or an NDJSON stream, that matches `predicate`. Every readable
+/// drug is part of the release, selected or not. Edges to drugs missing from
+/// the release are logged, and dropped when `drop_missing` is set. Drugs that
+/// can't be read are quarantined and skipped. Parquet is written when
+/// `output` ends in `.parquet`. Returns the number of edges written.
+pub fn interactions_csv(
+    input: &str,
+    output: &str,
+    drop_missing: bool,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let mut graph = InteractionGraph::default();
+    for record in crate::source::typed_records(input)? {
+        if let Some(drug) = record?.read::<Drug>(quarantine) {
+            if predicate.is_none_or(|p| p.matches(&drug)) {
+                graph.add(&drug);
+            } else {
+                graph.release(&drug);
+            }
+        }
+    }
+
+    let mut edges = graph.edges();
+    let missing = edges.iter().filter(|f| !f.in_release).count();
+    if missing > 0 {
+        log::warn!(
+            "{} interactions point to drugs missing from the release",
+            missing
+        );
+    }
+    if drop_missing {
+        edges.retain(|f| f.in_release);
+    }
+
+    save_to_file(output, &edges)?;
+
+    Ok(edges.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interaction_graph() {
+        let mut graph = InteractionGraph::default();
+        crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .map(|f| crate::model::from_str::<Drug>(&f.unwrap().to_string()).unwrap())
+            .for_each(|f| graph.add(&f));
+
+        let edges = graph.edges();
+        assert_eq!(edges.len(), 2);
+
+        assert_eq!(edges[0].drugbank_id_a, "DB00001");
+        assert_eq!(edges[0].drugbank_id_b, "DB00006");
+        assert_eq!(edges[0].name_b, "Bivalirudin");
+        assert!(edges[0].in_release);
+
+        assert_eq!(edges[1].drugbank_id_b, "DB99999");
+        assert!(!edges[1].in_release);
+    }
+
+    #[test]
+    fn test_interactions_csv_predicate() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let dir = dir.to_str().unwrap();
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            dir,
+            0,
+            &Default::default(),
+            &Quarantine::default(),
+        )
+        .unwrap();
+        let output = std::env::temp_dir().join(format!("{}.csv", uuid::Uuid::new_v4()));
+        let output = output.to_str().unwrap();
+
+        // Bivalirudin is left out by the predicate but still in the release
+        let predicate = Predicate::parse("id = DB00001").unwrap();
+        let count =
+            interactions_csv(dir, output, true, Some(&predicate), &Quarantine::default()).unwrap();
+        assert_eq!(count, 1);
+        let csv = std::fs::read_to_string(output).unwrap();
+        assert!(csv.contains("DB00001,Lepirudin,DB00006,Bivalirudin"));
+
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_file(output);
+    }
+}
@@ -0,0 +1,56 @@
+use mongodb::bson::{self, Document};
+
+use crate::{
+    db::Db,
+    error::{Result, ResultExt},
+    predicate::Predicate,
+    quarantine::{Quarantine, Stage},
+    source::{check_keys, records},
+};
+
+/// Inserts every drug of `path`, a split directory or an NDJSON stream, that
+/// matches `predicate` as-is into `table`, `batch` documents per round trip.
+/// Drugs that can't be read are quarantined and skipped. Returns the number
+/// of documents loaded.
+pub fn load_mongo(
+    path: &str,
+    table: &str,
+    batch: usize,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    log::info!("load {} into {}", path, table);
+    if predicate.is_some() {
+        check_keys(path)?;
+    }
+
+    let mut count = 0;
+    let mut docs = vec![];
+    for record in records(path)? {
+        let record = record?;
+        let value = match record.read_where(quarantine, predicate) {
+            Some(v) => v,
+            None => continue,
+        };
+        let doc = bson::to_document(&value).file(record.name());
+        docs.extend(record.check(quarantine, Stage::Parse, doc));
+        if docs.len() >= batch.max(1) {
+            count += insert(table, &mut docs)?;
+        }
+    }
+    count += insert(table, &mut docs)?;
+
+    Ok(count)
+}
+
+fn insert(table: &str, docs: &mut Vec<Document>) -> Result<usize> {
+    if docs.is_empty() {
+        return Ok(0);
+    }
+
+    let count = docs.len();
+    Db::insert_many(table, std::mem::take(docs))?;
+    log::info!("loaded {} documents", count);
+
+    Ok(count)
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::Conversion,
+    error::{Error, Result, ResultExt},
+};
+
+/// Written next to the split drug json files, skipped when they are read.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// One split drug json file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Path relative to the output directory.
+    pub path: String,
+    /// Hex sha256 of the file.
+    pub sha256: String,
+}
+
+/// Maps primary drugbank ids to the files `split` wrote them to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Number of id characters used as sub directory, 0 when not sharded.
+    pub shard: usize,
+    /// Attribute prefix the drugs were converted with, see `Conversion`.
+    #[serde(default = "default_attribute_prefix")]
+    pub attribute_prefix: String,
+    /// Text key the drugs were converted with.
+    #[serde(default = "default_text_key")]
+    pub text_key: String,
+    pub drugs: BTreeMap<String, Entry>,
+}
+
+fn default_attribute_prefix() -> String {
+    Conversion::default().attribute_prefix
+}
+
+fn default_text_key() -> String {
+    Conversion::default().text_key
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Hex sha256 of `bytes`.
+pub fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+impl Manifest {
+    pub fn new(shard: usize) -> Self {
+        Self {
+            shard,
+            attribute_prefix: default_attribute_prefix(),
+            text_key: default_text_key(),
+            drugs: BTreeMap::new(),
+        }
+    }
+
+    /// Whether the drugs use the keys the typed model reads.
+    pub fn typed(&self) -> bool {
+        self.attribute_prefix == default_attribute_prefix() && self.text_key == default_text_key()
+    }
+
+    /// Path of the file of `id` relative to the output directory, `DB00001.json`
+    /// or with `shard` 4 `DB00/DB00001.json`.
+    pub fn relative_path(&self, id: &str) -> String {
+        match self.shard {
+            0 => format!("{}.json", id),
+            n => format!("{}/{}.json", &id[..n.min(id.len())], id),
+        }
+    }
+
+    /// Reads `<dir>/manifest.json`.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(MANIFEST_FILE);
+        let file = File::open(&path).map_err(Error::io(&path))?;
+        serde_json::from_reader(file).file(&path.display().to_string())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let path = dir.as_ref().join(MANIFEST_FILE);
+        let file = File::create(&path).map_err(Error::io(&path))?;
+        serde_json::to_writer_pretty(file, self).file(&path.display().to_string())
+    }
+
+    /// Full path of the file of `id` below `dir`.
+    pub fn path<P: AsRef<Path>>(&self, dir: P, id: &str) -> Option<PathBuf> {
+        self.drugs.get(id).map(|f| dir.as_ref().join(&f.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        assert_eq!(Manifest::new(0).relative_path("DB00001"), "DB00001.json");
+        assert_eq!(
+            Manifest::new(5).relative_path("DB00001"),
+            "DB000/DB00001.json"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut manifest = Manifest::new(5);
+        manifest.drugs.insert(
+            "DB00001".to_string(),
+            Entry {
+                path: manifest.relative_path("DB00001"),
+                sha256: sha256(b"{}"),
+            },
+        );
+        manifest.save(&dir).unwrap();
+
+        let loaded = Manifest::load(&dir).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(
+            loaded.path(&dir, "DB00001"),
+            Some(dir.join("DB000/DB00001.json"))
+        );
+        assert_eq!(loaded.path(&dir, "DB99999"), None);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
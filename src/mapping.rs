@@ -0,0 +1,521 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::Conversion,
+    error::{Error, Result, ResultExt},
+    manifest::{Manifest, MANIFEST_FILE},
+    predicate::Predicate,
+    quarantine::Quarantine,
+    table::{Cell, Field, Kind, Table},
+};
+
+/// Declarative description of a CSV export.
+///
+/// ```yaml
+/// separator: "|"
+/// columns:
+///   - name: name
+///     path: name
+///   - name: gene
+///     path: targets.target.polypeptide.gene-name
+/// ```
+///
+/// A path is a dot separated list of keys into the converted drug json. Arrays
+/// met on the way are walked transparently, so a path works the same whether an
+/// element occurs once or many times. Every value found is joined with the
+/// column separator, falling back to the mapping separator.
+///
+/// The built-in path `$primary_id` selects the primary drugbank id, where
+/// `drugbank-id` gives every id of the drug.
+///
+/// Leaves with attributes give their text, read with the text key of the split
+/// manifest, see `Keys`. Attribute paths use the split's prefix, like `@id`.
+///
+/// Columns with `mode: explode` produce one row per repeated element instead.
+/// Explode columns sharing a path prefix stay aligned, e.g. `targets.target.id`
+/// and `targets.target.polypeptide.gene-name` give one row per
+/// drug–target–polypeptide, while join columns repeat on every row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mapping {
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub separator: Option<String>,
+    /// Drop line breaks from the extracted text.
+    #[serde(default)]
+    pub strip_newlines: bool,
+    #[serde(default)]
+    pub mode: Mode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// All values end up in one cell, joined by the separator.
+    #[default]
+    Join,
+    /// Each value gets its own row.
+    Explode,
+}
+
+impl Column {
+    fn cell(&self, mut values: Vec<String>, separator: &str) -> String {
+        if self.strip_newlines {
+            values = values
+                .into_iter()
+                .map(|f| f.replace("\r\n", "").replace('\n', ""))
+                .collect();
+        }
+        values.join(self.separator.as_deref().unwrap_or(separator))
+    }
+}
+
+/// Attribute prefix and text key of the drug json a mapping reads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keys {
+    pub attribute_prefix: String,
+    pub text_key: String,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        let conversion = Conversion::default();
+        Self {
+            attribute_prefix: conversion.attribute_prefix,
+            text_key: conversion.text_key,
+        }
+    }
+}
+
+impl Keys {
+    /// The keys recorded in the manifest of a split directory, the defaults
+    /// for streams, which are always split with them.
+    pub fn of(input: &str) -> Result<Self> {
+        let dir = Path::new(input);
+        if !dir.join(MANIFEST_FILE).exists() {
+            return Ok(Self::default());
+        }
+
+        let manifest = Manifest::load(dir)?;
+        Ok(Self {
+            attribute_prefix: manifest.attribute_prefix,
+            text_key: manifest.text_key,
+        })
+    }
+}
+
+/// Cells filled in by one exploded row, keyed by column index.
+type Partial = Vec<(usize, String)>;
+
+/// Path of the built-in primary drugbank id column.
+const PRIMARY_ID: &str = "$primary_id";
+
+fn default_separator() -> String {
+    "|".to_string()
+}
+
+impl Mapping {
+    /// Loads a mapping from a `.json` file, anything else is read as YAML.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::io(path))?;
+        let reader = BufReader::new(file);
+        let name = path.display().to_string();
+
+        if path.extension().is_some_and(|f| f == "json") {
+            serde_json::from_reader(reader).file(&name)
+        } else {
+            serde_yaml::from_reader(reader).file(&name)
+        }
+    }
+
+    pub fn headers(&self) -> Vec<&str> {
+        self.columns.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    /// Rows for one drug, a single row unless the mapping has explode columns.
+    pub fn rows(&self, drug: &Value) -> Vec<Vec<String>> {
+        self.rows_with(drug, &Keys::default())
+    }
+
+    /// `rows` of json converted with other keys.
+    pub fn rows_with(&self, drug: &Value, keys: &Keys) -> Vec<Vec<String>> {
+        let base: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| match c.mode {
+                Mode::Explode if c.path != PRIMARY_ID => String::new(),
+                _ => c.cell(select_with(drug, &c.path, keys), &self.separator),
+            })
+            .collect();
+
+        let exploded: Vec<(usize, Vec<&str>)> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.mode == Mode::Explode && c.path != PRIMARY_ID)
+            .map(|(i, c)| (i, segments(&c.path)))
+            .collect();
+        if exploded.is_empty() {
+            return vec![base];
+        }
+
+        let columns: Vec<(usize, &[&str])> =
+            exploded.iter().map(|(i, p)| (*i, p.as_slice())).collect();
+
+        expand(drug, &columns, &keys.text_key)
+            .into_iter()
+            .map(|partial| {
+                let mut row = base.clone();
+                for (i, text) in partial {
+                    row[i] = self.columns[i].cell(vec![text], &self.separator);
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('.').filter(|f| !f.is_empty()).collect()
+}
+
+/// Walks the explode columns together, branching once per array element.
+/// Columns under the same key share the branch, different keys multiply.
+fn expand(value: &Value, columns: &[(usize, &[&str])], text_key: &str) -> Vec<Partial> {
+    if let Value::Array(array) = value {
+        return array
+            .iter()
+            .flat_map(|f| expand(f, columns, text_key))
+            .collect();
+    }
+
+    let mut rows: Vec<Partial> = vec![columns
+        .iter()
+        .filter(|(_, p)| p.is_empty())
+        .filter_map(|(i, _)| text_with(value, text_key).map(|t| (*i, t)))
+        .collect()];
+
+    let mut keys: Vec<&str> = vec![];
+    for (_, path) in columns {
+        if let Some(key) = path.first() {
+            if !keys.contains(key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    for key in keys {
+        let nested: Vec<(usize, &[&str])> = columns
+            .iter()
+            .filter(|(_, p)| p.first() == Some(&key))
+            .map(|(i, p)| (*i, &p[1..]))
+            .collect();
+
+        let branch = match value.get(key) {
+            Some(child) => expand(child, &nested, text_key),
+            None => vec![vec![]],
+        };
+        let branch = if branch.is_empty() {
+            vec![vec![]]
+        } else {
+            branch
+        };
+
+        rows = rows
+            .iter()
+            .flat_map(|row| {
+                branch.iter().map(move |b| {
+                    let mut row = row.clone();
+                    row.extend(b.iter().cloned());
+                    row
+                })
+            })
+            .collect();
+    }
+
+    rows
+}
+
+/// Returns the text of every scalar reached by `path`.
+pub fn select(value: &Value, path: &str) -> Vec<String> {
+    select_with(value, path, &Keys::default())
+}
+
+/// `select` of json converted with other keys.
+pub fn select_with(value: &Value, path: &str, keys: &Keys) -> Vec<String> {
+    if path == PRIMARY_ID {
+        let id = crate::model::primary_id_with(
+            &value["drugbank-id"],
+            &keys.attribute_prefix,
+            &keys.text_key,
+        );
+        return if id.is_empty() { vec![] } else { vec![id] };
+    }
+
+    let mut found = vec![];
+    collect(value, &segments(path), &keys.text_key, &mut found);
+    found
+}
+
+fn collect(value: &Value, segments: &[&str], text_key: &str, found: &mut Vec<String>) {
+    if let Value::Array(array) = value {
+        array
+            .iter()
+            .for_each(|f| collect(f, segments, text_key, found));
+        return;
+    }
+
+    match segments.split_first() {
+        Some((key, rest)) => {
+            if let Some(child) = value.get(key) {
+                collect(child, rest, text_key, found);
+            }
+        }
+        None => {
+            if let Some(text) = text_with(value, text_key) {
+                found.push(text);
+            }
+        }
+    }
+}
+
+/// Text of a leaf, elements carrying attributes keep theirs under `#text`.
+pub fn text(value: &Value) -> Option<String> {
+    text_with(value, "#text")
+}
+
+/// `text` of json converted with another text key.
+pub fn text_with(value: &Value, text_key: &str) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Object(obj) => obj.get(text_key).and_then(|f| text_with(f, text_key)),
+        _ => None,
+    }
+}
+
+/// Writes the rows of every drug of `input`, a split directory or an NDJSON
+/// stream, that matches `predicate`, using `mapping`, as Parquet when
+/// `output` ends in `.parquet` and CSV otherwise. Drugs that can't be read
+/// are quarantined and skipped. Returns the number of rows written.
+///
+/// Split directories are read with the keys of their manifest, `predicate`
+/// needs the default ones.
+pub fn mapping_csv(
+    input: &str,
+    mapping: &Mapping,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let mut table = Table {
+        fields: mapping
+            .headers()
+            .iter()
+            .map(|f| Field::new(f, Kind::Utf8))
+            .collect(),
+        rows: vec![],
+    };
+
+    if predicate.is_some() {
+        crate::source::check_keys(input)?;
+    }
+    let keys = Keys::of(input)?;
+    for record in crate::source::records(input)? {
+        let drug: Value = match record?.read_where(quarantine, predicate) {
+            Some(d) => d,
+            None => continue,
+        };
+
+        table.rows.extend(
+            mapping
+                .rows_with(&drug, &keys)
+                .iter()
+                .map(|row| row.iter().map(|f| Cell::text(f)).collect::<Vec<_>>()),
+        );
+    }
+
+    table.save(output)?;
+
+    Ok(table.rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drugs() -> Vec<Value> {
+        crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .collect::<Result<Vec<Value>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mapping_row() {
+        let mapping: Mapping = serde_yaml::from_str(
+            r#"
+columns:
+  - name: id
+    path: drugbank-id
+    separator: ";"
+  - name: name
+    path: name
+  - name: gene
+    path: targets.target.polypeptide.gene-name
+  - name: uniprot
+    path: targets.target.polypeptide.@id
+  - name: toxicity
+    path: toxicity
+    strip_newlines: true
+  - name: primary
+    path: $primary_id
+"#,
+        )
+        .unwrap();
+
+        let drugs = drugs();
+        assert_eq!(
+            mapping.headers(),
+            vec!["id", "name", "gene", "uniprot", "toxicity", "primary"]
+        );
+        assert_eq!(
+            mapping.rows(&drugs[1]),
+            vec![vec![
+                "DB00006;APRD00379",
+                "Bivalirudin",
+                "F2|ITGA2B|ITGB3",
+                "P00734|P08514|P05106",
+                "Bleeding.Overdose may cause hemorrhage.",
+                "DB00006"
+            ]]
+        );
+        // <targets/> and <toxicity/> become empty objects
+        assert_eq!(mapping.rows(&drugs[2])[0][2..5], ["", "", ""]);
+    }
+
+    #[test]
+    fn test_mapping_explode() {
+        let mapping: Mapping = serde_yaml::from_str(
+            r#"
+columns:
+  - name: name
+    path: name
+  - name: target
+    path: targets.target.id
+    mode: explode
+  - name: gene
+    path: targets.target.polypeptide.gene-name
+    mode: explode
+  - name: group
+    path: groups.group
+"#,
+        )
+        .unwrap();
+
+        let drugs = drugs();
+        assert_eq!(
+            mapping.rows(&drugs[1]),
+            vec![
+                vec!["Bivalirudin", "BE0000048", "F2", "approved|investigational"],
+                vec![
+                    "Bivalirudin",
+                    "BE0004796",
+                    "ITGA2B",
+                    "approved|investigational"
+                ],
+                vec![
+                    "Bivalirudin",
+                    "BE0004796",
+                    "ITGB3",
+                    "approved|investigational"
+                ],
+            ]
+        );
+        assert_eq!(
+            mapping.rows(&drugs[0]),
+            vec![vec!["Lepirudin", "BE0000048", "F2", "approved"]]
+        );
+        // a drug without targets still gets a row
+        assert_eq!(
+            mapping.rows(&drugs[2]),
+            vec![vec!["Withdrawnol", "", "", "approved|withdrawn"]]
+        );
+    }
+
+    #[test]
+    fn test_mapping_csv_keys() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let split = dir.join("json");
+        let output = dir.join("out.csv");
+        let conversion: Conversion =
+            toml::from_str("attribute_prefix = \"\"\ntext_key = \"value\"").unwrap();
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            split.to_str().unwrap(),
+            0,
+            &conversion,
+            &Quarantine::default(),
+        )
+        .unwrap();
+
+        let mapping: Mapping = serde_yaml::from_str(
+            r#"
+columns:
+  - name: primary
+    path: $primary_id
+  - name: id
+    path: drugbank-id
+  - name: uniprot
+    path: targets.target.polypeptide.id
+    mode: explode
+"#,
+        )
+        .unwrap();
+        let input = split.to_str().unwrap();
+        let count = mapping_csv(
+            input,
+            &mapping,
+            output.to_str().unwrap(),
+            None,
+            &Quarantine::default(),
+        )
+        .unwrap();
+        assert_eq!(count, 5);
+        let csv = std::fs::read_to_string(&output).unwrap();
+        assert!(csv.contains("DB00006,DB00006|APRD00379,P08514\n"));
+
+        // the predicate reads the typed model
+        let predicate = Predicate::parse("approved").unwrap();
+        assert!(mapping_csv(
+            input,
+            &mapping,
+            output.to_str().unwrap(),
+            Some(&predicate),
+            &Quarantine::default(),
+        )
+        .is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_shipped_mapping() {
+        let mapping = Mapping::from_path("mappings/drug_target_gene.yaml").unwrap();
+        let rows = mapping.rows(&drugs()[1]);
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|f| f[0] == "DB00006"));
+    }
+}
@@ -1,321 +1,556 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Drugbank {
-    #[serde(rename = "@created")]
-    pub created: String,
-    #[serde(rename = "@type")]
-    pub type_field: String,
-    // #[serde(rename = "@updated")]
-    // pub updated: String,
-    // pub absorption: Absorption,
-    // #[serde(rename = "affected-organisms")]
-    // pub affected_organisms: AffectedOrganisms,
-    // #[serde(rename = "ahfs-codes")]
-    // pub ahfs_codes: AhfsCodes,
-    // #[serde(rename = "atc-codes")]
-    // pub atc_codes: AtcCodes,
-    #[serde(rename = "average-mass")]
-    pub average_mass: Option<f64>,
-    #[serde(rename = "calculated-properties")]
-    pub calculated_properties: Option<Properties>,
-    // pub carriers: Carriers,
-    // #[serde(rename = "cas-number")]
-    // pub cas_number: String,
-    // pub categories: Categories,
-    // pub clearance: Clearance,
-    // pub description: String,
-    // pub dosages: Dosages,
-    // #[serde(rename = "drug-interactions")]
-    // pub drug_interactions: DrugInteractions,
-    #[serde(rename = "drugbank-id")]
-    pub drugbank_id: ::serde_json::Value,
-    // pub enzymes: Enzymes,
-    #[serde(rename = "experimental-properties")]
-    pub experimental_properties: Option<Properties>,
-    // #[serde(rename = "external-identifiers")]
-    // pub external_identifiers: ExternalIdentifiers,
-    // #[serde(rename = "external-links")]
-    // pub external_links: ExternalLinks,
-    // #[serde(rename = "food-interactions")]
-    // pub food_interactions: FoodInteractions,
-    // #[serde(rename = "general-references")]
-    // pub general_references: GeneralReferences,
-    pub groups: Groups,
-    // #[serde(rename = "half-life")]
-    // pub half_life: HalfLife,
-    // pub indication: Indication,
-    // #[serde(rename = "international-brands")]
-    // pub international_brands: InternationalBrands,
-    // pub manufacturers: Manufacturers,
-    // #[serde(rename = "mechanism-of-action")]
-    // pub mechanism_of_action: MechanismOfAction,
-    // pub metabolism: Metabolism,
-    // pub mixtures: Mixtures,
-    // #[serde(rename = "monoisotopic-mass")]
-    // pub monoisotopic_mass: f64,
-    // pub name: String,
-    // pub packagers: Packagers,
-    // pub patents: Patents,
-    // pub pathways: Pathways,
-    // #[serde(rename = "pdb-entries")]
-    // pub pdb_entries: PdbEntries,
-    // pub pharmacodynamics: Pharmacodynamics,
-    // pub prices: Prices,
-    // pub products: Products,
-    // #[serde(rename = "protein-binding")]
-    // pub protein_binding: ProteinBinding,
-    // pub reactions: Reactions,
-    // #[serde(rename = "route-of-elimination")]
-    // pub route_of_elimination: RouteOfElimination,
-    // pub salts: Salts,
-    // #[serde(rename = "snp-adverse-drug-reactions")]
-    // pub snp_adverse_drug_reactions: SnpAdverseDrugReactions,
-    // #[serde(rename = "snp-effects")]
-    // pub snp_effects: SnpEffects,
-    // pub synonyms: Synonyms,
-    // #[serde(rename = "synthesis-reference")]
-    // pub synthesis_reference: SynthesisReference,
-    // pub targets: Targets,
-    // pub toxicity: Toxicity,
-    // pub transporters: Transporters,
-    // pub unii: String,
-    // #[serde(rename = "volume-of-distribution")]
-    // pub volume_of_distribution: VolumeOfDistribution,
+//! Typed model of the DrugBank 5.x `<drug>` element as converted to json by
+//! `split`.
+//!
+//! The structs are generated from `schema/drugbank.xsd`, a maintained subset
+//! of the DrugBank schema, into `generated.rs` by `xml2csv codegen`. Rerun
+//! it after carrying a schema change over to that file. This module adds
+//! the id helpers of `Drug` and the deserializers the structs use.
+//!
+//! Attributes are `@name` and element text next to attributes is `#text`.
+//! Every repeated element may be a single value or an array, and an empty
+//! element is `null` or `""`, so fields go through the lenient deserializers
+//! below: `text`, `node`, `one_or_many`, `number` and `flag`, and their
+//! `optional_` variants for the elements the schema lets drugs leave out.
+
+use std::{fmt, iter, marker::PhantomData, str::FromStr};
+
+use serde::{
+    de::{
+        self,
+        value::{MapAccessDeserializer, MapDeserializer, StringDeserializer},
+        DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+#[rustfmt::skip]
+mod generated;
+
+pub use generated::*;
+
+const TEXT: &str = "#text";
+
+/// An element of any shape: `null` and `""` are the default, a scalar is the
+/// value itself or, for structs, their `#text`, and objects are taken as is.
+///
+/// `json_types` turns numeric and boolean text into numbers and booleans,
+/// scalars are read back as text, and text as numbers or booleans where `T`
+/// needs them.
+struct Node<T>(T);
+
+impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for Node<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor(PhantomData))
+    }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Absorption {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AffectedOrganisms {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AhfsCodes {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AtcCodes {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Properties {
-    #[serde(rename = "property")]
-    pub property: Option<PropertyEnum>,
+struct NodeVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Default> Visitor<'de> for NodeVisitor<T> {
+    type Value = Node<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an element")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Node(T::default()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Node(T::default()))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        Node::deserialize(d)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        if v.is_empty() {
+            return Ok(Node(T::default()));
+        }
+
+        let plain: StringDeserializer<de::value::Error> = v.clone().into_deserializer();
+        if let Ok(t) = T::deserialize(plain) {
+            return Ok(Node(t));
+        }
+        // numbers and booleans read back from text
+        let scalar = match v.trim() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            t => t
+                .parse()
+                .map(Value::Number)
+                .unwrap_or_else(|_| Value::String(t.to_string())),
+        };
+        if let Ok(t) = T::deserialize(scalar) {
+            return Ok(Node(t));
+        }
+        // an element that may carry attributes but has none here
+        T::deserialize(MapDeserializer::new(iter::once((TEXT, v)))).map(Node)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        // `<source/>` is converted to `{}`
+        match map.next_key()? {
+            None => Ok(Node(T::default())),
+            Some(key) => {
+                let map = Peeked {
+                    key: Some(key),
+                    map,
+                };
+                T::deserialize(MapAccessDeserializer::new(map)).map(Node)
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum PropertyEnum {
-    ARR(Vec<Property>),
-    OO(Property),
+/// A map whose first key has been read already.
+struct Peeked<A> {
+    key: Option<String>,
+    map: A,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Property {
-    pub kind: String,
-    pub source: ::serde_json::Value,
-    pub value: ::serde_json::Value,
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Peeked<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Carriers {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Categories {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Clearance {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Dosages {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DrugInteractions {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DrugbankId {
-    #[serde(rename = "text")]
-    pub text: String,
-    #[serde(rename = "@primary")]
-    pub primary: bool,
+struct ManyVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Default> Visitor<'de> for ManyVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("one or many elements")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(vec![])
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(vec![])
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = vec![];
+        while let Some(Node(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        if v.is_empty() {
+            return Ok(vec![]);
+        }
+        NodeVisitor(PhantomData)
+            .visit_string(v)
+            .map(|Node(f)| vec![f])
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        match map.next_key()? {
+            None => Ok(vec![]),
+            Some(key) => NodeVisitor(PhantomData)
+                .visit_map(Peeked {
+                    key: Some(key),
+                    map,
+                })
+                .map(|Node(f)| vec![f]),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum DrugbankIdEnum {
-    Id(DrugbankId),
-    Name(String),
+/// Accepts a single element or an array of them, an empty element is an
+/// empty list.
+pub fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    deserializer.deserialize_any(ManyVisitor(PhantomData))
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Enzymes {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ExternalIdentifiers {
-    #[serde(rename = "external-identifier")]
-    pub external_identifier: ExternalIdentifier,
+/// A nested element, see `Node`.
+pub fn node<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Node::deserialize(deserializer).map(|Node(f)| f)
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ExternalIdentifier {
-    pub identifier: i64,
-    pub resource: String,
+struct TextVisitor;
+
+impl<'de> Visitor<'de> for TextVisitor {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("element text")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(String::new())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(String::new())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        text(d)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    /// The `#text` of an element with attributes.
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut text = String::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == TEXT {
+                text = map.next_value_seed(TextSeed)?;
+            } else {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+        }
+        Ok(text)
+    }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ExternalLinks {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FoodInteractions {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GeneralReferences {
-    pub articles: Articles,
-    pub attachments: Attachments,
-    pub links: Links,
-    pub textbooks: Textbooks,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Articles {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Attachments {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Links {}
+struct TextSeed;
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Textbooks {}
+impl<'de> DeserializeSeed<'de> for TextSeed {
+    type Value = String;
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Groups {
-    pub group: serde_json::Value,
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        text(d)
+    }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct HalfLife {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Indication {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct InternationalBrands {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Manufacturers {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MechanismOfAction {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Metabolism {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Mixtures {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Packagers {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Patents {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Pathways {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PdbEntries {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Pharmacodynamics {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Prices {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Products {}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ProteinBinding {}
+/// Element text, numbers and booleans included.
+pub fn text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    deserializer.deserialize_any(TextVisitor)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Reactions {}
+/// A number that may be given as text, `None` for an empty element.
+pub fn number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let s = text(deserializer)?;
+    match s.trim() {
+        "" => Ok(None),
+        s => s.parse().map(Some).map_err(de::Error::custom),
+    }
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct RouteOfElimination {}
+/// Text of an element that may be left out: `None` when it is, which
+/// `#[serde(default)]` takes care of, and `Some` even when it is empty.
+pub fn optional_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    text(deserializer).map(Some)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Salts {}
+/// `flag` of an element that may be left out, see `optional_text`.
+pub fn optional_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    flag(deserializer).map(Some)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SnpAdverseDrugReactions {}
+/// `node` of an element that may be left out, see `optional_text`.
+pub fn optional_node<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    node(deserializer).map(Some)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SnpEffects {}
+/// `true`/`false` as boolean or text, an empty element is `false`.
+pub fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match text(deserializer)?.trim() {
+        "true" => Ok(true),
+        "false" | "" => Ok(false),
+        s => Err(de::Error::custom(format!(
+            "expected true or false, found {:?}",
+            s
+        ))),
+    }
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Synonyms {}
+/// The `drugbank-id` flagged `primary`, or the first one listed.
+pub fn primary_id(v: &Value) -> String {
+    primary_id_with(v, "@", TEXT)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SynthesisReference {}
+/// `primary_id` of json converted with another attribute prefix and text key.
+pub fn primary_id_with(v: &Value, attribute_prefix: &str, text_key: &str) -> String {
+    let primary = format!("{}primary", attribute_prefix);
+    let text = |v: &Value| match v {
+        Value::Object(o) => o.get(text_key).and_then(crate::mapping::text),
+        v => crate::mapping::text(v),
+    };
+
+    match v {
+        Value::Array(ids) => ids
+            .iter()
+            .find(|f| f.get(&primary).and_then(crate::mapping::text).as_deref() == Some("true"))
+            .or_else(|| ids.first())
+            .and_then(text)
+            .unwrap_or_default(),
+        v => text(v).unwrap_or_default(),
+    }
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Targets {}
+/// `from_str` of a drug json that has been parsed already.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let error = Error::value(e.path().to_string(), e.into_inner().to_string());
+        match primary_id(&value["drugbank-id"]).as_str() {
+            "" => error,
+            id => error.with_drug(id),
+        }
+    })
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Toxicity {}
+/// Deserializes a drug json. Errors in the data carry the JSON path of the
+/// offending value and the primary id of the drug.
+pub fn from_str<T: DeserializeOwned>(json: &str) -> Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let e = e.into_inner();
+        let error = match e.classify() {
+            serde_json::error::Category::Data if path != "." => Error::value(path, e.to_string()),
+            _ => Error::Json(e),
+        };
+
+        let id = serde_json::from_str::<Value>(json)
+            .map(|f| primary_id(&f["drugbank-id"]))
+            .unwrap_or_default();
+        match id.as_str() {
+            "" => error,
+            id => error.with_drug(id),
+        }
+    })?;
+    deserializer.end()?;
+
+    Ok(value)
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Transporters {}
+impl Drug {
+    /// The id flagged `primary`, or the first one listed.
+    pub fn primary_id(&self) -> &str {
+        self.drugbank_id
+            .iter()
+            .find(|f| f.primary)
+            .or_else(|| self.drugbank_id.first())
+            .map(|f| f.text.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Every other id of the drug, legacy `BTD`/`APRD` ids and the like, in
+    /// listed order.
+    pub fn aliases(&self) -> Vec<String> {
+        let primary = self.primary_id();
+        let mut aliases: Vec<String> = vec![];
+        for f in &self.drugbank_id {
+            if !f.text.is_empty() && f.text != primary && !aliases.contains(&f.text) {
+                aliases.push(f.text.clone());
+            }
+        }
+        aliases
+    }
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VolumeOfDistribution {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Drug> {
+        crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .map(|f| from_str(&f.unwrap().to_string()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_model() {
+        let drugs = sample();
+
+        let lepirudin = &drugs[0];
+        assert_eq!(lepirudin.primary_id(), "DB00001");
+        assert_eq!(lepirudin.aliases(), vec!["BTD00024", "BIOD00024"]);
+        assert_eq!(lepirudin.drugbank_id.len(), 3);
+        assert!(!lepirudin.drugbank_id[2].primary);
+        assert_eq!(lepirudin.groups.group, vec!["approved"]);
+        assert_eq!(lepirudin.average_mass, None);
+        assert_eq!(lepirudin.state.as_deref(), Some("liquid"));
+        assert_eq!(
+            lepirudin.sequences.as_ref().unwrap().sequence[0].format,
+            "FASTA"
+        );
+        // elements the schema lets drugs leave out are `None` when missing
+        assert!(lepirudin.calculated_properties.is_none());
+        assert_eq!(
+            lepirudin.pathways.pathway[0].drugs.drug[1].drugbank_id,
+            "DB01373"
+        );
+        assert_eq!(lepirudin.experimental_properties.property.len(), 4);
+        assert_eq!(
+            lepirudin.external_identifiers.external_identifier[0].identifier,
+            "P01050"
+        );
+
+        let target = &lepirudin.targets.target[0];
+        assert_eq!(target.position, Some(1));
+        assert_eq!(target.actions.action, vec!["inhibitor"]);
+        assert_eq!(target.polypeptide[0].gene_name, "F2");
+        assert_eq!(target.polypeptide[0].organism.ncbi_taxonomy_id, "9606");
+
+        let bivalirudin = &drugs[1];
+        assert_eq!(bivalirudin.monoisotopic_mass, Some(2179.0));
+        assert_eq!(bivalirudin.targets.target[1].polypeptide.len(), 2);
+        assert!(bivalirudin.targets.target[1].actions.action.is_empty());
+        // numeric looking text comes back as text
+        assert_eq!(
+            bivalirudin.external_identifiers.external_identifier[0].identifier,
+            "16129704"
+        );
+
+        let withdrawnol = &drugs[2];
+        assert_eq!(withdrawnol.toxicity, "");
+        assert_eq!(withdrawnol.state, None);
+        assert!(withdrawnol.targets.target.is_empty());
+        assert!(withdrawnol.drug_interactions.drug_interaction.is_empty());
+    }
+
+    #[test]
+    fn test_lenient() {
+        let drug: Drug = from_str(
+            r##"{
+                "drugbank-id": ["BTD00010", {"@primary": "true", "#text": "DB00010"}],
+                "synonyms": {"synonym": [{"@language": "english", "#text": "A"}, "B"]},
+                "products": {"product": {"name": "P", "generic": "true", "approved": false}},
+                "salts": "",
+                "state": "",
+                "average-mass": "12.5",
+                "enzymes": {"enzyme": {"inhibition-strength": "moderate", "polypeptide": {
+                    "@id": "P05177", "synonyms": {"synonym": "CYPIA2"}, "theoretical-pi": 8.5
+                }}}
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(drug.primary_id(), "DB00010");
+        assert_eq!(drug.aliases(), vec!["BTD00010"]);
+        assert_eq!(drug.synonyms.synonym[0].language, "english");
+        assert_eq!(drug.synonyms.synonym[1].text, "B");
+        assert!(drug.products.product[0].generic);
+        assert!(drug.salts.salt.is_empty());
+        // an empty element is there, unlike a missing one
+        assert_eq!(drug.state.as_deref(), Some(""));
+        assert!(drug.fda_label.is_none());
+        assert_eq!(drug.average_mass, Some(12.5));
+        let enzyme = &drug.enzymes.enzyme[0];
+        assert_eq!(enzyme.inhibition_strength, "moderate");
+        assert_eq!(enzyme.polypeptide[0].synonyms.synonym, vec!["CYPIA2"]);
+        assert_eq!(enzyme.polypeptide[0].theoretical_pi, "8.5");
+
+        let e = from_str::<Drug>(
+            r#"{"drugbank-id": "DB00011", "targets": {"target": [{}, {"@position": "x"}]}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            e.location().unwrap().drugbank_id.as_deref(),
+            Some("DB00011")
+        );
+        assert!(e.to_string().contains("targets.target[1].@position"));
+    }
+}
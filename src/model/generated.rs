@@ -0,0 +1,1055 @@
+// Generated by `xml2csv codegen` from drugbank.xsd, do not edit.
+
+use serde::{Deserialize, Serialize};
+
+/// This is the root element type for the DrugBank database schema.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Drugbank {
+    /// The DrugBank version for the exported XML file.
+    #[serde(rename = "@version", deserialize_with = "crate::model::text")]
+    pub version: String,
+    /// The date the XML file was exported.
+    #[serde(rename = "@exported-on", deserialize_with = "crate::model::text")]
+    pub exported_on: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub drug: Vec<Drug>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Drug {
+    #[serde(rename = "@type", deserialize_with = "crate::model::text")]
+    pub type_field: String,
+    /// Date that this drug was first added to DrugBank.
+    #[serde(rename = "@created", deserialize_with = "crate::model::text")]
+    pub created: String,
+    /// Denotes when this drug was last updated in DrugBank.
+    #[serde(rename = "@updated", deserialize_with = "crate::model::text")]
+    pub updated: String,
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::one_or_many")]
+    pub drugbank_id: Vec<DrugbankDrugSaltId>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub description: String,
+    #[serde(rename = "cas-number", deserialize_with = "crate::model::text")]
+    pub cas_number: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub unii: String,
+    #[serde(rename = "average-mass", deserialize_with = "crate::model::number")]
+    pub average_mass: Option<f64>,
+    #[serde(rename = "monoisotopic-mass", deserialize_with = "crate::model::number")]
+    pub monoisotopic_mass: Option<f64>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub state: Option<String>,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub groups: GroupList,
+    #[serde(rename = "general-references", deserialize_with = "crate::model::node")]
+    pub general_references: ReferenceList,
+    #[serde(rename = "synthesis-reference", deserialize_with = "crate::model::text")]
+    pub synthesis_reference: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub indication: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub pharmacodynamics: String,
+    #[serde(rename = "mechanism-of-action", deserialize_with = "crate::model::text")]
+    pub mechanism_of_action: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub toxicity: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub metabolism: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub absorption: String,
+    #[serde(rename = "half-life", deserialize_with = "crate::model::text")]
+    pub half_life: String,
+    #[serde(rename = "protein-binding", deserialize_with = "crate::model::text")]
+    pub protein_binding: String,
+    #[serde(rename = "route-of-elimination", deserialize_with = "crate::model::text")]
+    pub route_of_elimination: String,
+    #[serde(rename = "volume-of-distribution", deserialize_with = "crate::model::text")]
+    pub volume_of_distribution: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub clearance: String,
+    #[serde(deserialize_with = "crate::model::optional_node")]
+    pub classification: Option<Classification>,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub salts: SaltList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub synonyms: SynonymList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub products: ProductList,
+    #[serde(rename = "international-brands", deserialize_with = "crate::model::node")]
+    pub international_brands: InternationalBrandList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub mixtures: MixtureList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub packagers: PackagerList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub manufacturers: ManufacturerList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub prices: PriceList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub categories: CategoryList,
+    #[serde(rename = "affected-organisms", deserialize_with = "crate::model::node")]
+    pub affected_organisms: AffectedOrganismList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub dosages: DosageList,
+    #[serde(rename = "atc-codes", deserialize_with = "crate::model::node")]
+    pub atc_codes: AtcCodeList,
+    #[serde(rename = "ahfs-codes", deserialize_with = "crate::model::node")]
+    pub ahfs_codes: AhfsCodeList,
+    #[serde(rename = "pdb-entries", deserialize_with = "crate::model::node")]
+    pub pdb_entries: PdbEntryList,
+    #[serde(rename = "fda-label", deserialize_with = "crate::model::optional_text")]
+    pub fda_label: Option<String>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub msds: Option<String>,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub patents: PatentList,
+    #[serde(rename = "food-interactions", deserialize_with = "crate::model::node")]
+    pub food_interactions: FoodInteractionList,
+    #[serde(rename = "drug-interactions", deserialize_with = "crate::model::node")]
+    pub drug_interactions: DrugInteractionList,
+    #[serde(deserialize_with = "crate::model::optional_node")]
+    pub sequences: Option<SequenceList>,
+    #[serde(rename = "calculated-properties", deserialize_with = "crate::model::optional_node")]
+    pub calculated_properties: Option<CalculatedPropertyList>,
+    #[serde(rename = "experimental-properties", deserialize_with = "crate::model::node")]
+    pub experimental_properties: ExperimentalPropertyList,
+    #[serde(rename = "external-identifiers", deserialize_with = "crate::model::node")]
+    pub external_identifiers: ExternalIdentifierList,
+    #[serde(rename = "external-links", deserialize_with = "crate::model::node")]
+    pub external_links: ExternalLinkList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub pathways: PathwayList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub reactions: ReactionList,
+    #[serde(rename = "snp-effects", deserialize_with = "crate::model::node")]
+    pub snp_effects: SnpEffectList,
+    #[serde(rename = "snp-adverse-drug-reactions", deserialize_with = "crate::model::node")]
+    pub snp_adverse_drug_reactions: SnpAdverseDrugReactionList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub targets: TargetList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub enzymes: EnzymeList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub carriers: CarrierList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub transporters: TransporterList,
+}
+
+/// The DrugBank ID is used to uniquely identify a drug or salt entry. There is
+/// a primary ID and several secondary IDs that come from older ID formats or
+/// merged entries.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrugbankDrugSaltId {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@primary", deserialize_with = "crate::model::flag")]
+    pub primary: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub group: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReferenceList {
+    #[serde(deserialize_with = "crate::model::node")]
+    pub articles: ArticleList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub textbooks: TextbookList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub links: LinkList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub attachments: AttachmentList,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArticleList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub article: Vec<Article>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Article {
+    #[serde(rename = "ref-id", deserialize_with = "crate::model::text")]
+    pub ref_id: String,
+    #[serde(rename = "pubmed-id", deserialize_with = "crate::model::text")]
+    pub pubmed_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub citation: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextbookList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub textbook: Vec<Textbook>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Textbook {
+    #[serde(rename = "ref-id", deserialize_with = "crate::model::text")]
+    pub ref_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub isbn: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub citation: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub link: Vec<Link>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Link {
+    #[serde(rename = "ref-id", deserialize_with = "crate::model::text")]
+    pub ref_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub title: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttachmentList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub attachment: Vec<Attachment>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attachment {
+    #[serde(rename = "ref-id", deserialize_with = "crate::model::text")]
+    pub ref_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub title: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub url: String,
+}
+
+/// Drug classification is obtained from ClassyFire
+/// (http://classyfire.wishartlab.com).
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Classification {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub description: String,
+    #[serde(rename = "direct-parent", deserialize_with = "crate::model::text")]
+    pub direct_parent: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub kingdom: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub superclass: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub class: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub subclass: String,
+    #[serde(rename = "alternative-parent", deserialize_with = "crate::model::one_or_many")]
+    pub alternative_parent: Vec<String>,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub substituent: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaltList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub salt: Vec<Salt>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Salt {
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::one_or_many")]
+    pub drugbank_id: Vec<DrugbankDrugSaltId>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub unii: String,
+    #[serde(rename = "cas-number", deserialize_with = "crate::model::text")]
+    pub cas_number: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub inchikey: String,
+    #[serde(rename = "average-mass", deserialize_with = "crate::model::number")]
+    pub average_mass: Option<f64>,
+    #[serde(rename = "monoisotopic-mass", deserialize_with = "crate::model::number")]
+    pub monoisotopic_mass: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SynonymList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub synonym: Vec<Synonym>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Synonym {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@language", deserialize_with = "crate::model::text")]
+    pub language: String,
+    #[serde(rename = "@coder", deserialize_with = "crate::model::text")]
+    pub coder: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub product: Vec<Product>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Product {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub labeller: String,
+    #[serde(rename = "ndc-id", deserialize_with = "crate::model::text")]
+    pub ndc_id: String,
+    #[serde(rename = "ndc-product-code", deserialize_with = "crate::model::text")]
+    pub ndc_product_code: String,
+    #[serde(rename = "dpd-id", deserialize_with = "crate::model::optional_text")]
+    pub dpd_id: Option<String>,
+    #[serde(rename = "ema-product-code", deserialize_with = "crate::model::text")]
+    pub ema_product_code: String,
+    #[serde(rename = "ema-ma-number", deserialize_with = "crate::model::text")]
+    pub ema_ma_number: String,
+    #[serde(rename = "started-marketing-on", deserialize_with = "crate::model::text")]
+    pub started_marketing_on: String,
+    #[serde(rename = "ended-marketing-on", deserialize_with = "crate::model::text")]
+    pub ended_marketing_on: String,
+    #[serde(rename = "dosage-form", deserialize_with = "crate::model::text")]
+    pub dosage_form: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub strength: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub route: String,
+    #[serde(rename = "fda-application-number", deserialize_with = "crate::model::text")]
+    pub fda_application_number: String,
+    #[serde(deserialize_with = "crate::model::flag")]
+    pub generic: bool,
+    #[serde(rename = "over-the-counter", deserialize_with = "crate::model::flag")]
+    pub over_the_counter: bool,
+    #[serde(deserialize_with = "crate::model::flag")]
+    pub approved: bool,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub country: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub source: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InternationalBrandList {
+    #[serde(rename = "international-brand", deserialize_with = "crate::model::one_or_many")]
+    pub international_brand: Vec<InternationalBrand>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InternationalBrand {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub company: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixtureList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub mixture: Vec<Mixture>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mixture {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub ingredients: String,
+    #[serde(rename = "supplemental-ingredients", deserialize_with = "crate::model::text")]
+    pub supplemental_ingredients: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackagerList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub packager: Vec<Packager>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Packager {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManufacturerList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub manufacturer: Vec<Manufacturer>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manufacturer {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@generic", deserialize_with = "crate::model::flag")]
+    pub generic: bool,
+    #[serde(rename = "@url", deserialize_with = "crate::model::text")]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub price: Vec<Price>,
+}
+
+/// The price for the given drug in US or Canadian currency.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Price {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub description: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub cost: Cost,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub unit: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cost {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@currency", deserialize_with = "crate::model::text")]
+    pub currency: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub category: Vec<Category>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Category {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub category: String,
+    #[serde(rename = "mesh-id", deserialize_with = "crate::model::text")]
+    pub mesh_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AffectedOrganismList {
+    #[serde(rename = "affected-organism", deserialize_with = "crate::model::one_or_many")]
+    pub affected_organism: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DosageList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub dosage: Vec<Dosage>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dosage {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub form: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub route: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub strength: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtcCodeList {
+    #[serde(rename = "atc-code", deserialize_with = "crate::model::one_or_many")]
+    pub atc_code: Vec<AtcCode>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtcCode {
+    #[serde(rename = "@code", deserialize_with = "crate::model::text")]
+    pub code: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub level: Vec<AtcCodeLevel>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtcCodeLevel {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@code", deserialize_with = "crate::model::text")]
+    pub code: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AhfsCodeList {
+    #[serde(rename = "ahfs-code", deserialize_with = "crate::model::one_or_many")]
+    pub ahfs_code: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdbEntryList {
+    #[serde(rename = "pdb-entry", deserialize_with = "crate::model::one_or_many")]
+    pub pdb_entry: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatentList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub patent: Vec<Patent>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Patent {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub number: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub country: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub approved: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub expires: String,
+    #[serde(rename = "pediatric-extension", deserialize_with = "crate::model::flag")]
+    pub pediatric_extension: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodInteractionList {
+    #[serde(rename = "food-interaction", deserialize_with = "crate::model::one_or_many")]
+    pub food_interaction: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrugInteractionList {
+    #[serde(rename = "drug-interaction", deserialize_with = "crate::model::one_or_many")]
+    pub drug_interaction: Vec<DrugInteraction>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrugInteraction {
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::node")]
+    pub drugbank_id: DrugbankDrugSaltId,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub description: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SequenceList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub sequence: Vec<SequenceListSequence>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SequenceListSequence {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@format", deserialize_with = "crate::model::text")]
+    pub format: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalculatedPropertyList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub property: Vec<CalculatedProperty>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalculatedProperty {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub kind: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub value: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub source: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperimentalPropertyList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub property: Vec<ExperimentalProperty>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperimentalProperty {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub kind: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub value: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub source: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalIdentifierList {
+    #[serde(rename = "external-identifier", deserialize_with = "crate::model::one_or_many")]
+    pub external_identifier: Vec<ExternalIdentifier>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalIdentifier {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub resource: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub identifier: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalLinkList {
+    #[serde(rename = "external-link", deserialize_with = "crate::model::one_or_many")]
+    pub external_link: Vec<ExternalLink>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalLink {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub resource: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathwayList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub pathway: Vec<Pathway>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pathway {
+    #[serde(rename = "smpdb-id", deserialize_with = "crate::model::text")]
+    pub smpdb_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub category: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub drugs: PathwayDrugList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub enzymes: PathwayEnzymeList,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathwayDrugList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub drug: Vec<PathwayDrug>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathwayDrug {
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::text")]
+    pub drugbank_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathwayEnzymeList {
+    #[serde(rename = "uniprot-id", deserialize_with = "crate::model::one_or_many")]
+    pub uniprot_id: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReactionList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub reaction: Vec<Reaction>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Reaction {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub sequence: String,
+    #[serde(rename = "left-element", deserialize_with = "crate::model::node")]
+    pub left_element: ReactionElement,
+    #[serde(rename = "right-element", deserialize_with = "crate::model::node")]
+    pub right_element: ReactionElement,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub enzymes: ReactionEnzymeList,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReactionElement {
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::text")]
+    pub drugbank_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReactionEnzymeList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub enzyme: Vec<ReactionEnzyme>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReactionEnzyme {
+    #[serde(rename = "drugbank-id", deserialize_with = "crate::model::text")]
+    pub drugbank_id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(rename = "uniprot-id", deserialize_with = "crate::model::text")]
+    pub uniprot_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnpEffectList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub effect: Vec<SnpEffect>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnpEffect {
+    #[serde(rename = "protein-name", deserialize_with = "crate::model::optional_text")]
+    pub protein_name: Option<String>,
+    #[serde(rename = "gene-symbol", deserialize_with = "crate::model::optional_text")]
+    pub gene_symbol: Option<String>,
+    #[serde(rename = "uniprot-id", deserialize_with = "crate::model::optional_text")]
+    pub uniprot_id: Option<String>,
+    #[serde(rename = "rs-id", deserialize_with = "crate::model::optional_text")]
+    pub rs_id: Option<String>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub allele: Option<String>,
+    #[serde(rename = "defining-change", deserialize_with = "crate::model::optional_text")]
+    pub defining_change: Option<String>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub description: Option<String>,
+    #[serde(rename = "pubmed-id", deserialize_with = "crate::model::optional_text")]
+    pub pubmed_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnpAdverseDrugReactionList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub reaction: Vec<SnpAdverseDrugReaction>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnpAdverseDrugReaction {
+    #[serde(rename = "protein-name", deserialize_with = "crate::model::optional_text")]
+    pub protein_name: Option<String>,
+    #[serde(rename = "gene-symbol", deserialize_with = "crate::model::optional_text")]
+    pub gene_symbol: Option<String>,
+    #[serde(rename = "uniprot-id", deserialize_with = "crate::model::optional_text")]
+    pub uniprot_id: Option<String>,
+    #[serde(rename = "rs-id", deserialize_with = "crate::model::optional_text")]
+    pub rs_id: Option<String>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub allele: Option<String>,
+    #[serde(rename = "adverse-reaction", deserialize_with = "crate::model::optional_text")]
+    pub adverse_reaction: Option<String>,
+    #[serde(deserialize_with = "crate::model::optional_text")]
+    pub description: Option<String>,
+    #[serde(rename = "pubmed-id", deserialize_with = "crate::model::optional_text")]
+    pub pubmed_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub target: Vec<Target>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Target {
+    #[serde(rename = "@position", deserialize_with = "crate::model::number")]
+    pub position: Option<i64>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub organism: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub actions: ActionList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub references: ReferenceList,
+    #[serde(rename = "known-action", deserialize_with = "crate::model::text")]
+    pub known_action: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub polypeptide: Vec<Polypeptide>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnzymeList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub enzyme: Vec<Enzyme>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Enzyme {
+    #[serde(rename = "@position", deserialize_with = "crate::model::number")]
+    pub position: Option<i64>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub organism: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub actions: ActionList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub references: ReferenceList,
+    #[serde(rename = "known-action", deserialize_with = "crate::model::text")]
+    pub known_action: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub polypeptide: Vec<Polypeptide>,
+    #[serde(rename = "inhibition-strength", deserialize_with = "crate::model::text")]
+    pub inhibition_strength: String,
+    #[serde(rename = "induction-strength", deserialize_with = "crate::model::text")]
+    pub induction_strength: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CarrierList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub carrier: Vec<Carrier>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Carrier {
+    #[serde(rename = "@position", deserialize_with = "crate::model::number")]
+    pub position: Option<i64>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub organism: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub actions: ActionList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub references: ReferenceList,
+    #[serde(rename = "known-action", deserialize_with = "crate::model::text")]
+    pub known_action: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub polypeptide: Vec<Polypeptide>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransporterList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub transporter: Vec<Transporter>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transporter {
+    #[serde(rename = "@position", deserialize_with = "crate::model::number")]
+    pub position: Option<i64>,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub id: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub organism: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub actions: ActionList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub references: ReferenceList,
+    #[serde(rename = "known-action", deserialize_with = "crate::model::text")]
+    pub known_action: String,
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub polypeptide: Vec<Polypeptide>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub action: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Polypeptide {
+    #[serde(rename = "@id", deserialize_with = "crate::model::text")]
+    pub id: String,
+    #[serde(rename = "@source", deserialize_with = "crate::model::text")]
+    pub source: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+    #[serde(rename = "general-function", deserialize_with = "crate::model::text")]
+    pub general_function: String,
+    #[serde(rename = "specific-function", deserialize_with = "crate::model::text")]
+    pub specific_function: String,
+    #[serde(rename = "gene-name", deserialize_with = "crate::model::text")]
+    pub gene_name: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub locus: String,
+    #[serde(rename = "cellular-location", deserialize_with = "crate::model::text")]
+    pub cellular_location: String,
+    #[serde(rename = "transmembrane-regions", deserialize_with = "crate::model::text")]
+    pub transmembrane_regions: String,
+    #[serde(rename = "signal-regions", deserialize_with = "crate::model::text")]
+    pub signal_regions: String,
+    #[serde(rename = "theoretical-pi", deserialize_with = "crate::model::text")]
+    pub theoretical_pi: String,
+    #[serde(rename = "molecular-weight", deserialize_with = "crate::model::text")]
+    pub molecular_weight: String,
+    #[serde(rename = "chromosome-location", deserialize_with = "crate::model::text")]
+    pub chromosome_location: String,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub organism: Organism,
+    #[serde(rename = "external-identifiers", deserialize_with = "crate::model::node")]
+    pub external_identifiers: PolypeptideExternalIdentifierList,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub synonyms: PolypeptideSynonymList,
+    #[serde(rename = "amino-acid-sequence", deserialize_with = "crate::model::node")]
+    pub amino_acid_sequence: Sequence,
+    #[serde(rename = "gene-sequence", deserialize_with = "crate::model::node")]
+    pub gene_sequence: Sequence,
+    #[serde(deserialize_with = "crate::model::node")]
+    pub pfams: PfamList,
+    #[serde(rename = "go-classifiers", deserialize_with = "crate::model::node")]
+    pub go_classifiers: GoClassifierList,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Organism {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@ncbi-taxonomy-id", deserialize_with = "crate::model::text")]
+    pub ncbi_taxonomy_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolypeptideExternalIdentifierList {
+    #[serde(rename = "external-identifier", deserialize_with = "crate::model::one_or_many")]
+    pub external_identifier: Vec<PolypeptideExternalIdentifier>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolypeptideExternalIdentifier {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub resource: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub identifier: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolypeptideSynonymList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub synonym: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sequence {
+    #[serde(rename = "#text", deserialize_with = "crate::model::text")]
+    pub text: String,
+    #[serde(rename = "@format", deserialize_with = "crate::model::text")]
+    pub format: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PfamList {
+    #[serde(deserialize_with = "crate::model::one_or_many")]
+    pub pfam: Vec<Pfam>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pfam {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub identifier: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoClassifierList {
+    #[serde(rename = "go-classifier", deserialize_with = "crate::model::one_or_many")]
+    pub go_classifier: Vec<GoClassifier>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoClassifier {
+    #[serde(deserialize_with = "crate::model::text")]
+    pub category: String,
+    #[serde(deserialize_with = "crate::model::text")]
+    pub description: String,
+}
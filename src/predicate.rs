@@ -0,0 +1,625 @@
+use std::{collections::HashSet, fmt, str::FromStr, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{Error, Result},
+    model::Drug,
+};
+
+/// A condition on drug records, parsed from expressions like
+/// `approved AND NOT withdrawn AND average-mass < 500 AND has InChIKey` or
+/// `logP between 0 and 5`.
+///
+/// - `field op value` compares with `=`, `!=`, `<`, `<=`, `>` or `>=`. A
+///   field with several values, like `groups` or a property listed by more
+///   than one source, matches when any of them does. `!=` is the negation of
+///   `=`.
+/// - `field between a and b` is `a <= field <= b`.
+/// - `has field` is true when the field has a non-empty value.
+/// - A bare word or quoted text is true when the drug is in that group or of
+///   that type, `approved` or `biotech`.
+/// - `not`, `and` and `or`, in decreasing precedence, and parentheses.
+///
+/// Fields are `type`, `groups`, `id` (primary and legacy ids), `name`,
+/// `state`, `cas-number`, `unii`, `average-mass`, `monoisotopic-mass` and
+/// otherwise the kind of a calculated or experimental property of the
+/// schema, only one of both with a `calculated.` or `experimental.` prefix.
+/// Kinds the schema doesn't list are given as `property.<kind>`, other names
+/// are rejected. Names, keywords and texts are compared ignoring case, names
+/// with spaces are quoted, `"Water Solubility" = soluble`.
+///
+/// Numeric comparisons only match values that are plain numbers, ranges like
+/// `236-238` and values with units like `3.1 mg/mL` never do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    Has(String),
+    /// Group or type.
+    Is(String),
+    Compare(String, Op, Literal),
+    Between(String, f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// The number and its text as written.
+    Number(f64, String),
+    Text(String),
+}
+
+impl Predicate {
+    /// Parses an expression, errors carry the column they were found at.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            end: expression.chars().count() + 1,
+        };
+        let predicate = parser.or()?;
+        match parser.peek() {
+            None => Ok(predicate),
+            Some((column, Token::RParen)) => Err(error(*column, "unmatched )")),
+            Some((column, t)) => Err(error(*column, format!("unexpected {}", t))),
+        }
+    }
+
+    pub fn matches(&self, drug: &Drug) -> bool {
+        match self {
+            Predicate::And(a, b) => a.matches(drug) && b.matches(drug),
+            Predicate::Or(a, b) => a.matches(drug) || b.matches(drug),
+            Predicate::Not(p) => !p.matches(drug),
+            Predicate::Has(field) => !values(drug, field).is_empty(),
+            Predicate::Is(name) => {
+                drug.type_field.eq_ignore_ascii_case(name)
+                    || drug
+                        .groups
+                        .group
+                        .iter()
+                        .any(|f| f.eq_ignore_ascii_case(name))
+            }
+            Predicate::Compare(field, Op::Ne, literal) => !values(drug, field)
+                .iter()
+                .any(|v| compare(field, v, Op::Eq, literal)),
+            Predicate::Compare(field, op, literal) => values(drug, field)
+                .iter()
+                .any(|v| compare(field, v, *op, literal)),
+            Predicate::Between(field, low, high) => values(drug, field)
+                .iter()
+                .filter_map(|f| number_of(field, f))
+                .any(|f| *low <= f && f <= *high),
+        }
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Predicate::parse(s)
+    }
+}
+
+/// Fields of the drug itself, see `values`.
+const FIELDS: &[&str] = &[
+    "type",
+    "group",
+    "groups",
+    "id",
+    "drugbank-id",
+    "name",
+    "state",
+    "cas-number",
+    "unii",
+    "average-mass",
+    "monoisotopic-mass",
+];
+
+/// Property kinds of `calculated-property-kind-type` in the schema.
+const CALCULATED_KINDS: &[&str] = &[
+    "logP",
+    "logS",
+    "Water Solubility",
+    "IUPAC Name",
+    "Traditional IUPAC Name",
+    "Molecular Weight",
+    "Monoisotopic Weight",
+    "SMILES",
+    "Molecular Formula",
+    "InChI",
+    "InChIKey",
+    "Polar Surface Area (PSA)",
+    "Refractivity",
+    "Polarizability",
+    "Rotatable Bond Count",
+    "H Bond Acceptor Count",
+    "H Bond Donor Count",
+    "pKa (strongest acidic)",
+    "pKa (strongest basic)",
+    "Physiological Charge",
+    "Number of Rings",
+    "Bioavailability",
+    "Rule of Five",
+    "Ghose Filter",
+    "MDDR-Like Rule",
+];
+
+/// Property kinds of `experimental-property-kind-type` in the schema.
+const EXPERIMENTAL_KINDS: &[&str] = &[
+    "Water Solubility",
+    "Melting Point",
+    "Boiling Point",
+    "logP",
+    "logS",
+    "Hydrophobicity",
+    "Isoelectric Point",
+    "caco2 Permeability",
+    "pKa",
+    "Molecular Weight",
+    "Molecular Formula",
+    "Radioactivity",
+];
+
+/// Whether `field` names a drug field or a property kind.
+fn known(field: &str) -> bool {
+    let contains = |kinds: &[&str], kind: &str| kinds.iter().any(|f| f.eq_ignore_ascii_case(kind));
+    match field.split_once('.') {
+        Some((p, kind)) if p.eq_ignore_ascii_case("calculated") => contains(CALCULATED_KINDS, kind),
+        Some((p, kind)) if p.eq_ignore_ascii_case("experimental") => {
+            contains(EXPERIMENTAL_KINDS, kind)
+        }
+        Some((p, kind)) if p.eq_ignore_ascii_case("property") => !kind.trim().is_empty(),
+        _ => {
+            contains(FIELDS, &field.to_lowercase().replace('_', "-"))
+                || contains(CALCULATED_KINDS, field)
+                || contains(EXPERIMENTAL_KINDS, field)
+        }
+    }
+}
+
+fn error(column: usize, message: impl Into<String>) -> Error {
+    Error::Predicate {
+        column,
+        message: message.into(),
+    }
+}
+
+fn compare(field: &str, value: &str, op: Op, literal: &Literal) -> bool {
+    match (op, literal) {
+        (Op::Eq, Literal::Text(text)) => value.eq_ignore_ascii_case(text),
+        (Op::Eq, Literal::Number(n, text)) => {
+            number(value) == Some(*n) || value.eq_ignore_ascii_case(text)
+        }
+        (_, Literal::Number(n, _)) => match number_of(field, value) {
+            Some(v) => match op {
+                Op::Lt => v < *n,
+                Op::Le => v <= *n,
+                Op::Gt => v > *n,
+                Op::Ge => v >= *n,
+                Op::Eq | Op::Ne => unreachable!("handled above"),
+            },
+            None => false,
+        },
+        // rejected by the parser
+        (_, Literal::Text(_)) => false,
+    }
+}
+
+/// `value` when it is a plain number, not a range or a number with a unit.
+fn number(value: &str) -> Option<f64> {
+    value.trim().parse().ok().filter(|f: &f64| f.is_finite())
+}
+
+/// Fields whose values have been reported as not a number.
+static NOT_NUMBERS: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+/// `number` of a value compared as a number, the first value of each field
+/// that isn't one is logged.
+fn number_of(field: &str, value: &str) -> Option<f64> {
+    let number = number(value);
+    if number.is_none() {
+        let mut reported = NOT_NUMBERS.lock().unwrap_or_else(|e| e.into_inner());
+        if reported.insert(field.to_lowercase()) {
+            log::warn!(
+                "{} : {:?} isn't a plain number, such values don't match numeric comparisons",
+                field,
+                value
+            );
+        }
+    }
+    number
+}
+
+/// The non-empty values of `field` of `drug`.
+fn values(drug: &Drug, field: &str) -> Vec<String> {
+    let name = field.to_lowercase().replace('_', "-");
+    let values = match &name[..] {
+        "type" => vec![drug.type_field.clone()],
+        "group" | "groups" => drug.groups.group.clone(),
+        "id" | "drugbank-id" => {
+            let mut ids = vec![drug.primary_id().to_string()];
+            ids.extend(drug.aliases());
+            ids
+        }
+        "name" => vec![drug.name.clone()],
+        "state" => drug.state.iter().cloned().collect(),
+        "cas-number" => vec![drug.cas_number.clone()],
+        "unii" => vec![drug.unii.clone()],
+        "average-mass" => drug.average_mass.iter().map(f64::to_string).collect(),
+        "monoisotopic-mass" => drug.monoisotopic_mass.iter().map(f64::to_string).collect(),
+        _ => {
+            let (calculated, experimental, kind) = match field.split_once('.') {
+                Some((p, kind)) if p.eq_ignore_ascii_case("calculated") => (true, false, kind),
+                Some((p, kind)) if p.eq_ignore_ascii_case("experimental") => (false, true, kind),
+                Some((p, kind)) if p.eq_ignore_ascii_case("property") => (true, true, kind),
+                _ => (true, true, field),
+            };
+            let calculated = drug
+                .calculated_properties
+                .iter()
+                .flat_map(|f| &f.property)
+                .filter(|_| calculated)
+                .map(|f| (&f.kind, &f.value));
+            let experimental = drug
+                .experimental_properties
+                .property
+                .iter()
+                .filter(|_| experimental)
+                .map(|f| (&f.kind, &f.value));
+            calculated
+                .chain(experimental)
+                .filter(|(k, _)| k.eq_ignore_ascii_case(kind))
+                .map(|(_, v)| v.clone())
+                .collect()
+        }
+    };
+
+    values
+        .into_iter()
+        .filter(|f| !f.trim().is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Op(Op),
+    /// Quoted text.
+    Text(String),
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::Text(s) => write!(f, "\"{}\"", s),
+            Token::Word(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Tokens with the 1-based column they start at.
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '=' if next == Some('=') => (Token::Op(Op::Eq), 2),
+            '=' => (Token::Op(Op::Eq), 1),
+            '!' if next == Some('=') => (Token::Op(Op::Ne), 2),
+            '<' if next == Some('>') => (Token::Op(Op::Ne), 2),
+            '<' if next == Some('=') => (Token::Op(Op::Le), 2),
+            '<' => (Token::Op(Op::Lt), 1),
+            '>' if next == Some('=') => (Token::Op(Op::Ge), 2),
+            '>' => (Token::Op(Op::Gt), 1),
+            '"' | '\'' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|f| *f == c)
+                    .ok_or_else(|| error(column, "unterminated text"))?;
+                let text = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Text(text), end + 2)
+            }
+            '!' => return Err(error(column, "unexpected !, use not or !=")),
+            _ => {
+                let len = chars[i..]
+                    .iter()
+                    .position(|f| f.is_whitespace() || "()=!<>\"'".contains(*f))
+                    .unwrap_or(chars.len() - i);
+                (Token::Word(chars[i..i + len].iter().collect()), len)
+            }
+        };
+        tokens.push((column, token));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    /// Column reported for errors at the end of the expression.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// Consumes the keyword if it comes next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some((_, Token::Word(w))) if w.eq_ignore_ascii_case(keyword) => {
+                self.index += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Predicate> {
+        let mut predicate = self.and()?;
+        while self.keyword("or") {
+            predicate = Predicate::Or(Box::new(predicate), Box::new(self.and()?));
+        }
+        Ok(predicate)
+    }
+
+    fn and(&mut self) -> Result<Predicate> {
+        let mut predicate = self.not()?;
+        while self.keyword("and") {
+            predicate = Predicate::And(Box::new(predicate), Box::new(self.not()?));
+        }
+        Ok(predicate)
+    }
+
+    fn not(&mut self) -> Result<Predicate> {
+        if self.keyword("not") {
+            return Ok(Predicate::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Predicate> {
+        if self.keyword("has") {
+            return Ok(Predicate::Has(self.field("a field after has")?));
+        }
+        let column = self.peek().map_or(self.end, |f| f.0);
+        if let Some((_, Token::LParen)) = self.peek() {
+            self.index += 1;
+            let predicate = self.or()?;
+            return match self.next() {
+                Some((_, Token::RParen)) => Ok(predicate),
+                _ => Err(error(column, "unclosed (")),
+            };
+        }
+
+        let name_column = self.column();
+        let name = self.name("a field or group")?;
+        let field = |name: String| {
+            if known(&name) {
+                Ok(name)
+            } else {
+                Err(error(name_column, format!("unknown field {}", name)))
+            }
+        };
+        if self.keyword("between") {
+            let name = field(name)?;
+            let low = self.number("a number after between")?;
+            if !self.keyword("and") {
+                return Err(error(self.column(), "expected and after between"));
+            }
+            let high = self.number("a number after and")?;
+            return Ok(Predicate::Between(name, low, high));
+        }
+        let op = match self.peek() {
+            Some((_, Token::Op(op))) => *op,
+            _ => return Ok(Predicate::Is(name)),
+        };
+        let name = field(name)?;
+        self.index += 1;
+
+        let column = self.column();
+        let literal = match self.next() {
+            Some((_, Token::Word(w))) => match w.parse::<f64>() {
+                Ok(n) => Literal::Number(n, w),
+                Err(_) => Literal::Text(w),
+            },
+            Some((_, Token::Text(t))) => Literal::Text(t),
+            _ => return Err(error(column, "expected a value")),
+        };
+        if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) && matches!(literal, Literal::Text(_)) {
+            return Err(error(column, "expected a number"));
+        }
+
+        Ok(Predicate::Compare(name, op, literal))
+    }
+
+    fn column(&self) -> usize {
+        self.peek().map_or(self.end, |f| f.0)
+    }
+
+    fn name(&mut self, expected: &str) -> Result<String> {
+        let column = self.column();
+        match self.next() {
+            Some((_, Token::Word(w))) | Some((_, Token::Text(w))) => Ok(w),
+            _ => Err(error(column, format!("expected {}", expected))),
+        }
+    }
+
+    /// `name` that has to be a known field.
+    fn field(&mut self, expected: &str) -> Result<String> {
+        let column = self.column();
+        let name = self.name(expected)?;
+        if !known(&name) {
+            return Err(error(column, format!("unknown field {}", name)));
+        }
+        Ok(name)
+    }
+
+    fn number(&mut self, expected: &str) -> Result<f64> {
+        let column = self.column();
+        match self.next() {
+            Some((_, Token::Word(w))) => w
+                .parse()
+                .map_err(|_| error(column, format!("expected {}", expected))),
+            _ => Err(error(column, format!("expected {}", expected))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drugs() -> Vec<Drug> {
+        crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .map(|f| crate::model::from_str::<Drug>(&f.unwrap().to_string()).unwrap())
+            .collect()
+    }
+
+    fn selected(expression: &str) -> Vec<String> {
+        let predicate = Predicate::parse(expression).unwrap();
+        drugs()
+            .iter()
+            .filter(|f| predicate.matches(f))
+            .map(|f| f.primary_id().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_predicate() {
+        assert_eq!(
+            selected("approved AND NOT withdrawn AND average-mass < 500 AND has InChIKey"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            selected("approved and not withdrawn and has inchikey"),
+            vec!["DB00006"]
+        );
+        assert_eq!(selected("logP between 0 and 5"), vec!["DB00002"]);
+        assert_eq!(selected("calculated.logP < 0"), vec!["DB00006"]);
+        assert_eq!(selected("experimental.logP < 0"), Vec::<String>::new());
+        assert_eq!(
+            selected("type = biotech or (groups = withdrawn)"),
+            vec!["DB00001", "DB00002"]
+        );
+        assert_eq!(selected("groups != approved"), Vec::<String>::new());
+        assert_eq!(
+            selected("'small molecule' and not investigational"),
+            vec!["DB00002"]
+        );
+        assert_eq!(selected("\"Isoelectric Point\" >= 4.04"), vec!["DB00001"]);
+        assert_eq!(selected("id = btd00024"), vec!["DB00001"]);
+        assert_eq!(selected("average-mass = 180.16"), vec!["DB00002"]);
+        // plain numbers compare as numbers however they are written
+        assert_eq!(selected("\"Melting Point\" = 65.0"), vec!["DB00001"]);
+        // text values never match a numeric comparison
+        assert_eq!(selected("\"Water Solubility\" > 0"), Vec::<String>::new());
+        assert_eq!(selected("property.Hydrophobicity < 0"), vec!["DB00001"]);
+
+        let e = Predicate::parse("approved and (logP > 1").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "where expression error at column 14 : unclosed ("
+        );
+        let e = Predicate::parse("logP between 0 5").unwrap_err();
+        assert!(e.to_string().contains("column 16 : expected and"));
+        assert!(Predicate::parse("average-mass < heavy").is_err());
+        assert!(Predicate::parse("approved)").is_err());
+        let e = Predicate::parse("logP < < 3").unwrap_err();
+        assert!(e.to_string().contains("column 8 : expected a value"));
+        assert!(Predicate::parse("name = 'open").is_err());
+        // misspelled fields are rejected instead of matching nothing
+        let e = Predicate::parse("approved and averge-mass < 500").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "where expression error at column 14 : unknown field averge-mass"
+        );
+        let e = Predicate::parse("not grups = approved").unwrap_err();
+        assert!(e.to_string().contains("column 5 : unknown field grups"));
+        assert!(Predicate::parse("has inchi-key").is_err());
+        assert!(Predicate::parse("'calculated.Melting Point' > 0").is_err());
+        assert!(Predicate::parse("'experimental.Melting Point' > 0").is_ok());
+        assert!(Predicate::parse("").is_err());
+    }
+
+    #[test]
+    fn test_number_values() {
+        let drug: Drug = crate::model::from_str(
+            r#"{"drugbank-id": "DB00010", "experimental-properties": {"property": [
+                {"kind": "Melting Point", "value": "236-238"},
+                {"kind": "Water Solubility", "value": "3.1 mg/mL"},
+                {"kind": "logP", "value": " 2.5 "}
+            ]}}"#,
+        )
+        .unwrap();
+        let matches = |expression: &str| Predicate::parse(expression).unwrap().matches(&drug);
+
+        // a range is neither of its bounds
+        assert!(!matches("\"Melting Point\" >= 236"));
+        assert!(!matches("\"Melting Point\" < 300"));
+        assert!(!matches("\"Melting Point\" between 200 and 300"));
+        assert!(matches("\"Melting Point\" = 236-238"));
+        // nor is a value with a unit its number
+        assert!(!matches("\"Water Solubility\" > 3"));
+        assert!(!matches("\"Water Solubility\" = 3.1"));
+        assert!(!matches("\"Water Solubility\" between 0 and 10"));
+        assert!(matches("\"Water Solubility\" = '3.1 mg/mL'"));
+        assert!(matches("logP between 2 and 3"));
+        assert!(matches("logP = 2.5"));
+
+        assert_eq!(number("1e3"), Some(1000.0));
+        assert_eq!(number("NaN"), None);
+    }
+}
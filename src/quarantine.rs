@@ -0,0 +1,278 @@
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result, ResultExt};
+
+/// Pipeline step a record failed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// XML to JSON conversion of a drug.
+    Split,
+    /// Reading or deserializing a drug json.
+    Parse,
+    /// Extracting the fields of a record.
+    Extract,
+    /// Writing a record to a store or database.
+    Save,
+}
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Split => "split",
+            Stage::Parse => "parse",
+            Stage::Extract => "extract",
+            Stage::Save => "save",
+        }
+    }
+}
+
+/// One line of the error report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Failure {
+    pub path: String,
+    pub drugbank_id: String,
+    pub stage: Stage,
+    pub error: String,
+}
+
+/// Collects the records a run had to drop.
+///
+/// With a directory, every failing record is copied to `<dir>/<stage>/` as it
+/// happens, and `finish` writes `report.json` and `report.csv` next to them,
+/// empty reports included so that one from an earlier run isn't mistaken for
+/// the current one. The stage directories are emptied when the quarantine is
+/// created for the same reason.
+#[derive(Debug, Default)]
+pub struct Quarantine {
+    dir: Option<PathBuf>,
+    max_failures: Option<usize>,
+    failures: Mutex<Vec<Failure>>,
+}
+
+impl Quarantine {
+    pub fn new(dir: Option<&str>, max_failures: Option<usize>) -> Result<Self> {
+        let dir = dir.map(PathBuf::from);
+        if let Some(dir) = &dir {
+            for stage in [Stage::Split, Stage::Parse, Stage::Extract, Stage::Save] {
+                let path = dir.join(stage.name());
+                if path.exists() {
+                    std::fs::remove_dir_all(&path).map_err(Error::io(&path))?;
+                }
+            }
+        }
+
+        Ok(Self {
+            dir,
+            max_failures,
+            failures: Mutex::new(vec![]),
+        })
+    }
+
+    /// Passes `Ok` values through, records errors and turns them into `None`.
+    /// `source` is the input file that gets quarantined.
+    pub fn check<T>(&self, stage: Stage, source: Option<&str>, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.record(stage, source, None, &e);
+                None
+            }
+        }
+    }
+
+    /// Records a failure. The quarantined copy is `source` when given,
+    /// otherwise `content` is written under `name`.
+    pub fn record(
+        &self,
+        stage: Stage,
+        source: Option<&str>,
+        content: Option<(&str, &[u8])>,
+        error: &Error,
+    ) {
+        log::error!("{} : {}", stage.name(), error);
+
+        let location = error.location().cloned().unwrap_or_default();
+        let path = location
+            .file
+            .or_else(|| source.map(|f| f.to_string()))
+            .unwrap_or_default();
+        let failure = Failure {
+            path,
+            drugbank_id: location.drugbank_id.unwrap_or_default(),
+            stage,
+            error: error.to_string(),
+        };
+
+        if let Err(e) = self.keep(stage, source, content) {
+            log::error!("quarantine error : {}", e);
+        }
+
+        self.failures.lock().unwrap().push(failure);
+    }
+
+    fn keep(
+        &self,
+        stage: Stage,
+        source: Option<&str>,
+        content: Option<(&str, &[u8])>,
+    ) -> Result<()> {
+        let dir = match &self.dir {
+            Some(d) => d.join(stage.name()),
+            None => return Ok(()),
+        };
+        create_dir_all(&dir).map_err(Error::io(&dir))?;
+
+        match (source, content) {
+            (Some(source), _) => {
+                let name = Path::new(source).file_name().unwrap_or_default();
+                std::fs::copy(source, dir.join(name)).map_err(Error::io(source))?;
+            }
+            (None, Some((name, bytes))) => {
+                std::fs::write(dir.join(name), bytes).map_err(Error::io(dir.join(name)))?;
+            }
+            (None, None) => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn failures(&self) -> Vec<Failure> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// Logs the summary, writes the report and fails when there are more
+    /// failures than allowed.
+    pub fn finish(&self) -> Result<()> {
+        let failures = self.failures();
+
+        if failures.is_empty() {
+            log::info!("no records quarantined");
+        } else {
+            let mut stages = BTreeMap::new();
+            for f in &failures {
+                *stages.entry(f.stage.name()).or_insert(0) += 1;
+            }
+            let stages = stages
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            log::warn!("{} records failed ({})", failures.len(), stages);
+        }
+
+        if let Some(dir) = &self.dir {
+            self.write_report(dir, &failures)?;
+            if !failures.is_empty() {
+                log::warn!("quarantined records and report are in {}", dir.display());
+            }
+        }
+
+        match self.max_failures {
+            Some(max) if failures.len() > max => Err(Error::TooManyFailures {
+                failed: failures.len(),
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_report(&self, dir: &Path, failures: &[Failure]) -> Result<()> {
+        create_dir_all(dir).map_err(Error::io(dir))?;
+
+        let json = dir.join("report.json");
+        let file = File::create(&json).map_err(Error::io(&json))?;
+        serde_json::to_writer_pretty(file, failures).file(&json.display().to_string())?;
+
+        let csv = dir.join("report.csv");
+        let name = csv.display().to_string();
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(&csv)
+            .file(&name)?;
+        // written by hand so that an empty report still has its header
+        writer
+            .write_record(["path", "drugbank_id", "stage", "error"])
+            .file(&name)?;
+        for f in failures {
+            writer.serialize(f).file(&name)?;
+        }
+        writer.flush().map_err(Error::io(&csv))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarantine() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let source = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&source, "{").unwrap();
+        let source = source.to_str().unwrap();
+
+        let quarantine = Quarantine::new(dir.to_str(), Some(1)).unwrap();
+        assert_eq!(quarantine.check(Stage::Parse, Some(source), Ok(1)), Some(1));
+        // a clean run leaves an empty report
+        assert!(quarantine.finish().is_ok());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report.json")).unwrap(),
+            "[]"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report.csv")).unwrap(),
+            "path,drugbank_id,stage,error\n"
+        );
+
+        let result: Result<()> = crate::filter::read_drug(source);
+        assert!(quarantine
+            .check(Stage::Parse, Some(source), result)
+            .is_none());
+        assert!(quarantine.finish().is_ok());
+
+        let error = Error::value("groups.group[0]", "expected text").with_drug("DB00001");
+        quarantine.record(Stage::Split, None, Some(("7.xml", b"<drug/>")), &error);
+        assert!(matches!(
+            quarantine.finish(),
+            Err(Error::TooManyFailures { failed: 2, max: 1 })
+        ));
+
+        let failures = quarantine.failures();
+        assert_eq!(failures[0].path, source);
+        assert_eq!(failures[1].drugbank_id, "DB00001");
+        assert_eq!(failures[1].stage, Stage::Split);
+
+        let name = Path::new(source).file_name().unwrap();
+        assert!(dir.join("parse").join(name).exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("split/7.xml")).unwrap(),
+            "<drug/>"
+        );
+        let report: serde_json::Value =
+            serde_json::from_reader(File::open(dir.join("report.json")).unwrap()).unwrap();
+        assert_eq!(report[1]["stage"], "split");
+        let csv = std::fs::read_to_string(dir.join("report.csv")).unwrap();
+        assert!(csv.starts_with("path,drugbank_id,stage,error\n"));
+        assert_eq!(csv.lines().count(), 3);
+
+        // a new run starts without the copies of the previous one
+        let quarantine = Quarantine::new(dir.to_str(), None).unwrap();
+        assert!(!dir.join("parse").exists());
+        assert!(!dir.join("split").exists());
+        assert!(dir.join("report.json").exists());
+        assert!(quarantine.finish().is_ok());
+
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_file(source);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{fs::create_dir_all, io::Write, path::Path};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compress::Writer,
+    error::{Error, Result},
+    model::Drug,
+    predicate::Predicate,
+    quarantine::Quarantine,
+    source::typed_records,
+    table::{save_to_file, Cell, Field, Kind, Tabular},
+};
+
+/// Residues per line of written FASTA.
+const FASTA_WIDTH: usize = 60;
+
+/// One sequence of a biotech drug.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DrugSequence {
+    pub drugbank_id: String,
+    pub name: String,
+    /// Header line of the sequence without `>`, `DB00001 sequence`.
+    pub header: String,
+    pub sequence: String,
+    pub length: i64,
+}
+
+impl Tabular for DrugSequence {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("drugbank_id", Kind::Utf8),
+            Field::new("name", Kind::Utf8),
+            Field::new("header", Kind::Utf8),
+            Field::new("sequence", Kind::Utf8),
+            Field::new("length", Kind::Int64),
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::text(&self.drugbank_id),
+            Cell::text(&self.name),
+            Cell::text(&self.header),
+            Cell::text(&self.sequence),
+            Cell::I64(self.length),
+        ]
+    }
+}
+
+/// Splits FASTA text into header and residue pairs, whitespace removed.
+/// Text without a header line is a single unnamed sequence.
+pub fn parse_fasta(text: &str) -> Vec<(String, String)> {
+    let mut sequences: Vec<(String, String)> = vec![];
+    for line in text.lines().map(str::trim).filter(|f| !f.is_empty()) {
+        match line.strip_prefix('>') {
+            Some(header) => sequences.push((header.trim().to_string(), String::new())),
+            None => {
+                if sequences.is_empty() {
+                    sequences.push((String::new(), String::new()));
+                }
+                let last = sequences.len() - 1;
+                sequences[last]
+                    .1
+                    .extend(line.chars().filter(|c| !c.is_whitespace()));
+            }
+        }
+    }
+
+    sequences
+}
+
+/// The sequences of a biotech drug, other drugs have none.
+pub fn sequences_of(drug: &Drug) -> Vec<DrugSequence> {
+    if drug.type_field != "biotech" {
+        return vec![];
+    }
+
+    let id = drug.primary_id();
+    drug.sequences
+        .iter()
+        .flat_map(|f| &f.sequence)
+        .filter(|f| f.format.is_empty() || f.format.eq_ignore_ascii_case("FASTA"))
+        .flat_map(|f| parse_fasta(&f.text))
+        .filter(|(_, sequence)| !sequence.is_empty())
+        .map(|(header, sequence)| DrugSequence {
+            drugbank_id: id.to_string(),
+            name: drug.name.clone(),
+            header,
+            length: sequence.len() as i64,
+            sequence,
+        })
+        .collect()
+}
+
+/// Sequences of every biotech drug in `input`, a split directory or an
+/// NDJSON stream, that matches `predicate`, sorted by drugbank id.
+fn sequences(
+    input: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<Vec<DrugSequence>> {
+    let sequences = typed_records(input)?
+        .par_bridge()
+        .map(|record| {
+            Ok(record?
+                .read::<Drug>(quarantine)
+                .filter(|drug| predicate.is_none_or(|p| p.matches(drug)))
+                .map(|drug| sequences_of(&drug))
+                .unwrap_or_default())
+        })
+        .collect::<Result<Vec<Vec<DrugSequence>>>>()?;
+
+    let mut sequences: Vec<DrugSequence> = sequences.into_iter().flatten().collect();
+    // stable, the sequences of a drug keep their order
+    sequences.sort_by(|a, b| a.drugbank_id.cmp(&b.drugbank_id));
+
+    Ok(sequences)
+}
+
+fn write_fasta(output: &str, sequences: &[DrugSequence]) -> Result<()> {
+    if let Some(dir) = Path::new(output)
+        .parent()
+        .filter(|f| !f.as_os_str().is_empty())
+    {
+        create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+
+    let mut writer = Writer::create(output)?;
+    for f in sequences {
+        let header = match f.header.strip_prefix(&f.drugbank_id) {
+            Some(rest) => format!("{}|{}{}", f.drugbank_id, f.name, rest),
+            None => format!("{}|{} {}", f.drugbank_id, f.name, f.header),
+        };
+        writeln!(writer, ">{}", header.trim_end()).map_err(Error::io(output))?;
+        for line in f.sequence.as_bytes().chunks(FASTA_WIDTH) {
+            writer.write_all(line).map_err(Error::io(output))?;
+            writer.write_all(b"\n").map_err(Error::io(output))?;
+        }
+    }
+
+    writer.finish().map_err(Error::io(output))
+}
+
+/// Whether `output` names a FASTA file, compressed or not.
+fn is_fasta(output: &str) -> bool {
+    let name = output.to_lowercase();
+    let name = [".gz", ".zst", ".bz2"]
+        .iter()
+        .find_map(|f| name.strip_suffix(f))
+        .unwrap_or(&name);
+
+    [".fasta", ".fa", ".faa"].iter().any(|f| name.ends_with(f))
+}
+
+/// Writes the sequences of the biotech drugs in `input` that match
+/// `predicate` as FASTA when `output` ends in `.fasta`, `.fa` or `.faa`,
+/// optionally compressed, as Parquet when it ends in `.parquet` and CSV
+/// otherwise. Returns the number of sequences written.
+pub fn sequences_to_file(
+    input: &str,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    let sequences = sequences(input, predicate, quarantine)?;
+    if is_fasta(output) {
+        write_fasta(output, &sequences)?;
+    } else {
+        save_to_file(output, &sequences)?;
+    }
+
+    Ok(sequences.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            parse_fasta(">a one\nMKV\nLL \n>b\nGG\n"),
+            vec![
+                ("a one".to_string(), "MKVLL".to_string()),
+                ("b".to_string(), "GG".to_string())
+            ]
+        );
+        assert_eq!(parse_fasta("MKV"), vec![(String::new(), "MKV".to_string())]);
+
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let json = dir.join("json");
+        let json = json.to_str().unwrap();
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            json,
+            0,
+            &Default::default(),
+            &Quarantine::default(),
+        )
+        .unwrap();
+
+        let fasta = dir.join("sequences.fasta.gz");
+        let fasta = fasta.to_str().unwrap();
+        assert_eq!(
+            sequences_to_file(json, fasta, None, &Quarantine::default()).unwrap(),
+            1
+        );
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut crate::compress::open(fasta).unwrap(), &mut text)
+            .unwrap();
+        assert_eq!(
+            text,
+            ">DB00001|Lepirudin sequence\n\
+             LVYTDCTESGQNLCLCEGSNVCGQGNKCILGSDGEKNQCVTGEGTPKPQSHNDGDFEEIP\n\
+             EEYLQ\n"
+        );
+
+        let csv = dir.join("sequences.csv");
+        let csv = csv.to_str().unwrap();
+        sequences_to_file(json, csv, None, &Quarantine::default()).unwrap();
+        let text = std::fs::read_to_string(csv).unwrap();
+        assert!(text.starts_with("drugbank_id,name,header,sequence,length\n"));
+        assert!(text.contains("DB00001,Lepirudin,DB00001 sequence,LVYT"));
+        assert!(text.ends_with(",65\n"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
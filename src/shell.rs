@@ -18,7 +18,7 @@ impl Shell {
     }
 
     pub fn run(&self, command: &str) -> Result<String, String> {
-        if !std::fs::metadata(&self.path).is_ok() {
+        if std::fs::metadata(&self.path).is_err() {
             let result = create_dir(&self.path);
             if result.is_err() {
                 error!("create dir error");
@@ -30,7 +30,7 @@ impl Shell {
             };
         }
 
-        let path = format!("{}/{}.sh", self.path, Uuid::new_v4().to_string());
+        let path = format!("{}/{}.sh", self.path, Uuid::new_v4());
 
         if let Ok(mut file) = File::create(&path) {
             file.write_all(command.as_bytes()).expect("write failed");
@@ -46,12 +46,12 @@ impl Shell {
 
         match result {
             Ok(output) => {
-                if output.status.code().unwrap() != 0 {
+                if !output.status.success() {
                     let err = String::from_utf8_lossy(&output.stderr).to_string();
                     // if !err.is_empty() {
                     //     warn!("stderr: {}", err);
                     // }
-                    Err(format!("{}", err))
+                    Err(err)
                 } else {
                     Ok(String::from_utf8_lossy(&output.stdout).to_string())
                 }
@@ -0,0 +1,228 @@
+use std::{io::BufRead, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    model::Drug,
+    predicate::Predicate,
+    quarantine::{Quarantine, Stage},
+};
+
+/// One drug json of the input: a file of a split directory or a line of an
+/// NDJSON stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    File(String),
+    Line {
+        /// `<stream>:<line number>`, used as file in errors.
+        name: String,
+        line: usize,
+        bytes: Vec<u8>,
+    },
+}
+
+impl Record {
+    pub fn name(&self) -> &str {
+        match self {
+            Record::File(f) => f,
+            Record::Line { name, .. } => name,
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T> {
+        match self {
+            Record::File(f) => crate::filter::read_drug(f),
+            Record::Line { name, bytes, .. } => {
+                let text = crate::encoding::decode(bytes).file(name)?;
+                crate::model::from_str(&text).file(name)
+            }
+        }
+    }
+
+    /// Passes `Ok` values through, quarantines the record on errors.
+    pub fn check<T>(&self, quarantine: &Quarantine, stage: Stage, result: Result<T>) -> Option<T> {
+        match (self, result) {
+            (_, Ok(v)) => Some(v),
+            (Record::File(f), Err(e)) => {
+                quarantine.record(stage, Some(f), None, &e);
+                None
+            }
+            (Record::Line { line, bytes, .. }, Err(e)) => {
+                let name = format!("{}.json", line);
+                quarantine.record(stage, None, Some((&name, bytes)), &e);
+                None
+            }
+        }
+    }
+
+    /// `parse` and `check` at the parse stage.
+    pub fn read<T: DeserializeOwned>(&self, quarantine: &Quarantine) -> Option<T> {
+        self.check(quarantine, Stage::Parse, self.parse())
+    }
+
+    /// The json of the drug when `predicate` selects it, always without one.
+    /// The record is parsed once, the predicate sees it through the typed
+    /// model. Drugs that can't be read either way are quarantined.
+    pub fn read_where(
+        &self,
+        quarantine: &Quarantine,
+        predicate: Option<&Predicate>,
+    ) -> Option<Value> {
+        let value: Value = self.read(quarantine)?;
+        if let Some(p) = predicate {
+            let drug = crate::model::from_value::<Drug>(&value).file(self.name());
+            if !p.matches(&self.check(quarantine, Stage::Parse, drug)?) {
+                return None;
+            }
+        }
+        Some(value)
+    }
+}
+
+pub type Records = Box<dyn Iterator<Item = Result<Record>> + Send>;
+
+/// The drugs of `input`: the json files of a split directory, see
+/// `find_json_files`, otherwise the lines of an NDJSON file, which may be
+/// compressed or zipped. Blank lines are skipped.
+pub fn records(input: &str) -> Result<Records> {
+    if Path::new(input).is_dir() {
+        let files = crate::filter::find_json_files(input)?;
+        return Ok(Box::new(files.into_iter().map(|f| Ok(Record::File(f)))));
+    }
+
+    let reader = crate::compress::open(input)?;
+    let input = input.to_string();
+    Ok(Box::new(reader.split(b'\n').enumerate().filter_map(
+        move |(i, line)| {
+            let bytes = match line {
+                Ok(b) => b,
+                Err(e) => return Some(Err(Error::io(&input)(e))),
+            };
+            if bytes.iter().all(|f| f.is_ascii_whitespace()) {
+                return None;
+            }
+            let line = i + 1;
+            Some(Ok(Record::Line {
+                name: format!("{}:{}", input, line),
+                line,
+                bytes,
+            }))
+        },
+    )))
+}
+
+/// `records` for exports that read the typed model. Split directories whose
+/// manifest has other attribute or text keys than the model are refused, their
+/// drugs would come out empty.
+pub fn typed_records(input: &str) -> Result<Records> {
+    check_keys(input)?;
+    records(input)
+}
+
+/// Fails when `input` is a split directory with keys the typed model can't
+/// read, see `Manifest::typed`.
+pub fn check_keys(input: &str) -> Result<()> {
+    let dir = Path::new(input);
+    if !dir.join(crate::manifest::MANIFEST_FILE).exists() {
+        return Ok(());
+    }
+
+    let manifest = crate::manifest::Manifest::load(dir)?;
+    if manifest.typed() {
+        return Ok(());
+    }
+    Err(Error::Config(format!(
+        "{} was split with attribute prefix {:?} and text key {:?}, typed exports and \
+         --where need the defaults",
+        input, manifest.attribute_prefix, manifest.text_key
+    )))
+}
+
+/// Whether `path` names an NDJSON stream, `.ndjson` or `.jsonl` with an
+/// optional `.gz`/`.zst`/`.bz2`.
+pub fn is_stream(path: &str) -> bool {
+    let path = [".gz", ".zst", ".bz2"]
+        .iter()
+        .find_map(|f| path.strip_suffix(f))
+        .unwrap_or(path);
+    path.ends_with(".ndjson") || path.ends_with(".jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn test_records() {
+        assert!(is_stream("data/drugs.ndjson.zst"));
+        assert!(is_stream("data/drugs.jsonl"));
+        assert!(!is_stream("data/json"));
+
+        let path = std::env::temp_dir().join(format!("{}.ndjson.gz", uuid::Uuid::new_v4()));
+        let mut writer = crate::compress::Writer::create(&path).unwrap();
+        writer
+            .write_all(b"{\"name\": \"a\"}\n\n{\"name\": \n")
+            .unwrap();
+        writer.finish().unwrap();
+        let path = path.to_str().unwrap();
+
+        let records = records(path).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].name(), format!("{}:3", path));
+
+        let quarantine = Quarantine::default();
+        let value: serde_json::Value = records[0].read(&quarantine).unwrap();
+        assert_eq!(value["name"], "a");
+        assert!(records[1].read::<serde_json::Value>(&quarantine).is_none());
+        assert_eq!(quarantine.failures()[0].path, format!("{}:3", path));
+
+        // the predicate reads the typed model, the raw json is kept
+        let predicate = Predicate::parse("name = a").unwrap();
+        assert_eq!(
+            records[0]
+                .read_where(&quarantine, Some(&predicate))
+                .unwrap()["name"],
+            "a"
+        );
+        let record = Record::Line {
+            name: "odd:1".to_string(),
+            line: 1,
+            bytes: br#"{"name": "a", "groups": {"group": [{"odd": 1}]}}"#.to_vec(),
+        };
+        assert!(record.read_where(&quarantine, None).is_some());
+        assert!(record.read_where(&quarantine, Some(&predicate)).is_none());
+        assert!(quarantine.failures()[1].error.contains("groups.group[0]"));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_records_manifest() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        // left over from an earlier split
+        std::fs::write(dir.join("DB99999.json"), "{}").unwrap();
+        let dir = dir.to_str().unwrap();
+        crate::split::xml2json(
+            "tests/fixtures/drugbank_sample.xml",
+            dir,
+            0,
+            &Default::default(),
+            &Quarantine::default(),
+        )
+        .unwrap();
+
+        let names: Vec<String> = records(dir)
+            .unwrap()
+            .map(|f| f.unwrap().name().to_string())
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().all(|f| !f.ends_with("DB99999.json")));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
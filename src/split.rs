@@ -0,0 +1,672 @@
+use std::{
+    fs::create_dir_all,
+    io::{BufRead, Write},
+    path::Path,
+};
+
+use quick_xml::{events::Event, Reader, Writer};
+use quickxml_to_serde::{xml_string_to_json, Config, JsonArray, JsonType, NullValue};
+use serde_json::Value;
+
+use crate::{
+    arrays::ArrayPaths,
+    config::{Conversion, EmptyElements, ValueType},
+    error::{Error, Result, ResultExt},
+    manifest::{sha256, Entry, Manifest},
+    quarantine::{Quarantine, Stage},
+};
+
+const DRUG_TAG: &[u8] = b"drug";
+
+/// Streams the top-level `<drug>` elements of a DrugBank dump one at a time.
+///
+/// Only the XML of the drug currently being converted is kept in memory, so
+/// peak usage is bounded by the largest single drug instead of the whole file.
+pub struct DrugReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    depth: usize,
+    config: Config,
+    arrays: ArrayPaths,
+    finished: bool,
+    /// Byte range of the current drug in the decoded input.
+    span: (usize, usize),
+    /// XML of the last drug that failed to convert, when it is still known.
+    failed_xml: Option<String>,
+}
+
+impl DrugReader<Box<dyn BufRead>> {
+    /// Opens a dump in any encoding `encoding::detect` recognises, non UTF-8
+    /// input is transcoded while reading. Compressed dumps and zip releases
+    /// are read without unpacking them first, see `compress::open`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let reader = crate::compress::open(&path)?;
+        let name = path.as_ref().display().to_string();
+        Ok(Self::new(crate::encoding::utf8_reader(reader, &name)?))
+    }
+}
+
+impl<R: BufRead> DrugReader<R> {
+    pub fn new(reader: R) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.trim_text(false);
+
+        Self {
+            reader,
+            buf: Vec::new(),
+            depth: 0,
+            config: xml_config(&Conversion::default()),
+            arrays: ArrayPaths::default(),
+            finished: false,
+            span: (0, 0),
+            failed_xml: None,
+        }
+    }
+
+    /// Converts drugs as `conversion` says instead of with the defaults.
+    pub fn with_conversion(mut self, conversion: &Conversion) -> Self {
+        self.config = xml_config(conversion);
+        self.arrays = ArrayPaths::new(&conversion.arrays);
+        self
+    }
+
+    /// Takes the XML of the drug whose conversion just failed.
+    pub fn take_failed_xml(&mut self) -> Option<String> {
+        self.failed_xml.take()
+    }
+
+    fn error(&self, e: quick_xml::Error) -> Error {
+        Error::Xml {
+            position: self.reader.buffer_position(),
+            message: format!("{:?}", e),
+        }
+    }
+
+    /// A conversion error of the current drug, which is named by its byte
+    /// range.
+    fn convert_error(&self, message: impl std::fmt::Display) -> Error {
+        let (start, end) = self.span;
+        Error::Convert(format!(
+            "{} in the drug at bytes {}..{}",
+            message, start, end
+        ))
+    }
+
+    /// The drug is read completely by now, so invalid UTF-8 only fails this
+    /// drug. Its XML is kept with the bad bytes replaced.
+    fn utf8(&mut self, xml: Vec<u8>) -> Result<String> {
+        String::from_utf8(xml).map_err(|e| {
+            let error = self.convert_error(e.utf8_error());
+            self.failed_xml = Some(String::from_utf8_lossy(e.as_bytes()).into_owned());
+            error
+        })
+    }
+
+    /// Skips forward to the next `<drug>` directly below the root element and
+    /// returns its serialized XML.
+    fn next_drug_xml(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            self.buf.clear();
+            self.span.0 = self.reader.buffer_position();
+            let event = match self.reader.read_event(&mut self.buf) {
+                Ok(e) => e.into_owned(),
+                Err(e) => return Err(self.error(e)),
+            };
+
+            match event {
+                Event::Start(e) => {
+                    self.depth += 1;
+                    if self.depth == 2 && e.name() == DRUG_TAG {
+                        return self.capture(Event::Start(e)).map(Some);
+                    }
+                }
+                Event::Empty(e) if self.depth == 1 && e.name() == DRUG_TAG => {
+                    let mut writer = Writer::new(Vec::new());
+                    writer
+                        .write_event(Event::Empty(e))
+                        .map_err(|e| self.error(e))?;
+                    self.span.1 = self.reader.buffer_position();
+                    return Ok(Some(writer.into_inner()));
+                }
+                Event::End(_) => self.depth -= 1,
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// Copies every event up to the matching `</drug>` into a standalone document.
+    fn capture(&mut self, start: Event<'static>) -> Result<Vec<u8>> {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_event(start).map_err(|e| self.error(e))?;
+
+        let mut level = 1;
+        while level > 0 {
+            self.buf.clear();
+            let event = match self.reader.read_event(&mut self.buf) {
+                Ok(e) => e.into_owned(),
+                Err(e) => return Err(self.error(e)),
+            };
+
+            match event {
+                Event::Start(_) => level += 1,
+                Event::End(_) => level -= 1,
+                Event::Eof => {
+                    return Err(Error::Xml {
+                        position: self.reader.buffer_position(),
+                        message: "unexpected end of file inside <drug>".to_string(),
+                    })
+                }
+                _ => {}
+            }
+
+            writer.write_event(event).map_err(|e| self.error(e))?;
+        }
+        self.depth -= 1;
+        self.span.1 = self.reader.buffer_position();
+
+        Ok(writer.into_inner())
+    }
+
+    /// Converts the drug, consuming its XML. Errors name the byte range of the
+    /// drug since the XML is gone by then.
+    fn convert(&self, xml: String) -> Result<Value> {
+        let mut json = xml_string_to_json(xml, &self.config)
+            .map_err(|e| self.convert_error(format!("{:?}", e)))?;
+
+        let mut drug = json
+            .as_object_mut()
+            .and_then(|f| f.remove("drug"))
+            .ok_or_else(|| self.convert_error("converted json has no drug element"))?;
+        self.arrays.apply("drug", &mut drug);
+
+        Ok(drug)
+    }
+}
+
+fn xml_config(conversion: &Conversion) -> Config {
+    let empty = match conversion.empty_elements {
+        EmptyElements::Object => NullValue::EmptyObject,
+        EmptyElements::Null => NullValue::Null,
+        EmptyElements::Ignore => NullValue::Ignore,
+    };
+    let mut config = Config::new_with_custom_values(
+        conversion.leading_zero_as_string,
+        &conversion.attribute_prefix,
+        &conversion.text_key,
+        empty,
+    );
+    for (path, kind) in conversion.types() {
+        let kind = match kind {
+            ValueType::String => JsonType::AlwaysString,
+            ValueType::Bool => JsonType::Bool(vec!["true"]),
+            ValueType::Infer => JsonType::Infer,
+        };
+        config = config.add_json_type_override(&path, JsonArray::Infer(kind));
+    }
+    config
+}
+
+impl<R: BufRead> Iterator for DrugReader<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.next_drug_xml() {
+            Ok(Some(xml)) => Some(self.utf8(xml).and_then(|xml| self.convert(xml))),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                // the reader can't resync after a syntax error, stop here
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Splits a DrugBank XML dump into one JSON file per drug named by its
+/// primary drugbank id, returns the number of drugs written.
+///
+/// With `shard` > 0 files go into sub directories named by the first `shard`
+/// characters of the id. `manifest.json` maps the ids to their files and
+/// checksums.
+///
+/// When `output` is an `.ndjson`/`.jsonl` file, optionally ending in `.gz` or
+/// `.zst`, all drugs are written to it one per line instead.
+///
+/// `conversion` sets attribute and text keys, empty element handling, the
+/// JSON types of paths, identifiers are kept as strings by default, and the
+/// elements that are always written as arrays.
+///
+/// Other attribute prefixes and text keys than `@` and `#text` are recorded in
+/// the manifest, where the typed exports check them, so they are refused for
+/// NDJSON output.
+///
+/// A drug that fails to convert is quarantined with its byte range in the
+/// error, one without a usable id as `<index>.json`, and skipped. Syntax
+/// errors in the XML end the split since the reader can't resync.
+pub fn xml2json(
+    input: &str,
+    output: &str,
+    shard: usize,
+    conversion: &Conversion,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    if !conversion.typed_keys() {
+        if crate::source::is_stream(output) {
+            return Err(Error::Config(format!(
+                "{} : the typed exports can't read attribute prefix {:?} and text key {:?}, \
+                 split to a directory to keep them",
+                output, conversion.attribute_prefix, conversion.text_key
+            )));
+        }
+        log::warn!(
+            "{} can't be read by the typed exports or --where, only by mapping and load-mongo",
+            output
+        );
+    }
+
+    let mut stream = if crate::source::is_stream(output) {
+        if let Some(dir) = Path::new(output).parent() {
+            create_dir_all(dir).map_err(Error::io(dir))?;
+        }
+        Some(crate::compress::Writer::create(output)?)
+    } else {
+        create_dir_all(output).map_err(Error::io(output))?;
+        None
+    };
+
+    let mut reader = DrugReader::from_path(input)?.with_conversion(conversion);
+    let mut manifest = Manifest::new(shard);
+    manifest.attribute_prefix = conversion.attribute_prefix.clone();
+    manifest.text_key = conversion.text_key.clone();
+    for index in 0.. {
+        let drug = match reader.next() {
+            None => break,
+            Some(Ok(d)) => d,
+            Some(Err(e @ Error::Xml { .. })) => return Err(e).file(input),
+            Some(Err(e)) => {
+                let name = format!("{}.xml", index);
+                let xml = reader.take_failed_xml();
+                let e = e.with_file(&format!("{}#{}", input, index));
+                quarantine.record(
+                    Stage::Split,
+                    None,
+                    xml.as_ref().map(|f| (name.as_str(), f.as_bytes())),
+                    &e,
+                );
+                continue;
+            }
+        };
+
+        let id = crate::model::primary_id_with(
+            &drug["drugbank-id"],
+            &conversion.attribute_prefix,
+            &conversion.text_key,
+        );
+        if let Err(e) = check_id(&id, &manifest) {
+            let e = e.with_file(&format!("{}#{}", input, index));
+            let json = serde_json::to_vec(&drug).unwrap_or_default();
+            quarantine.record(
+                Stage::Split,
+                None,
+                Some((&format!("{}.json", index), &json)),
+                &e,
+            );
+            continue;
+        }
+
+        let mut json = serde_json::to_vec(&drug).drug(&id)?;
+        let relative = match &mut stream {
+            Some(writer) => {
+                json.push(b'\n');
+                writer.write_all(&json).map_err(Error::io(output))?;
+                format!("{}:{}", output, manifest.drugs.len() + 1)
+            }
+            None => {
+                let relative = manifest.relative_path(&id);
+                let path = Path::new(output).join(&relative);
+                log::info!("write to file {} ", path.display());
+
+                if let Some(dir) = path.parent() {
+                    create_dir_all(dir).map_err(Error::io(dir))?;
+                }
+                std::fs::write(&path, &json).map_err(Error::io(&path))?;
+                relative
+            }
+        };
+        manifest.drugs.insert(
+            id,
+            Entry {
+                path: relative,
+                sha256: sha256(&json),
+            },
+        );
+    }
+
+    match stream {
+        Some(writer) => writer.finish().map_err(Error::io(output))?,
+        None => manifest.save(output)?,
+    }
+
+    Ok(manifest.drugs.len())
+}
+
+/// Ids end up in file names, so only plain unique ids are accepted.
+fn check_id(id: &str, manifest: &Manifest) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|f| f.is_ascii_alphanumeric()) {
+        return Err(Error::value(
+            "drugbank-id",
+            format!("expected a primary id, found {:?}", id),
+        ));
+    }
+    if manifest.drugs.contains_key(id) {
+        return Err(Error::value("drugbank-id", format!("duplicate id {}", id)).with_drug(id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+
+    const SAMPLE: &str = "tests/fixtures/drugbank_sample.xml";
+
+    #[test]
+    fn test_drug_reader() {
+        let drugs = DrugReader::from_path(SAMPLE)
+            .unwrap()
+            .collect::<Result<Vec<Value>>>()
+            .unwrap();
+
+        // nested <drug> elements inside pathways are not top-level drugs
+        assert_eq!(drugs.len(), 3);
+        assert_eq!(drugs[0]["name"], "Lepirudin");
+        assert_eq!(drugs[1]["@type"], "small molecule");
+        assert_eq!(
+            drugs[0]["pathways"]["pathway"]["drugs"]["drug"][1]["name"],
+            "Calcium"
+        );
+    }
+
+    #[test]
+    fn test_drug_reader_truncated() {
+        let xml = "<drugbank><drug><name>a</name></drug><drug><name>b</name>";
+        let result: Vec<_> = DrugReader::new(xml.as_bytes()).collect();
+
+        assert_eq!(result.len(), 2);
+        assert!(result[0].is_ok());
+        assert!(result[1].is_err());
+    }
+
+    #[test]
+    fn test_drug_reader_convert_error() {
+        let xml =
+            "<drugbank>\n<drug><name>a</name></drug>\n<drug><name>&bogus;</name></drug></drugbank>";
+        let result: Vec<_> = DrugReader::new(xml.as_bytes()).collect();
+
+        assert_eq!(result.len(), 2);
+        assert!(result[0].is_ok());
+        let e = result[1].as_ref().unwrap_err().to_string();
+        assert!(e.ends_with("in the drug at bytes 39..72"), "{}", e);
+    }
+
+    #[test]
+    fn test_drug_reader_invalid_utf8() {
+        let xml =
+            b"<drugbank><drug><name>a\xFF</name></drug><drug><name>b</name></drug></drugbank>";
+        let mut reader = DrugReader::new(&xml[..]);
+
+        // only the drug holding the bad byte fails, reading goes on
+        let e = reader.next().unwrap().unwrap_err();
+        assert!(matches!(e, Error::Convert(_)), "{}", e);
+        assert_eq!(
+            reader.take_failed_xml().unwrap(),
+            "<drug><name>a\u{FFFD}</name></drug>"
+        );
+        assert_eq!(reader.next().unwrap().unwrap()["name"], "b");
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_drug_reader_conversion() {
+        let drugs = DrugReader::from_path(SAMPLE)
+            .unwrap()
+            .collect::<Result<Vec<Value>>>()
+            .unwrap();
+        // identifiers stay strings, other numbers are inferred
+        assert_eq!(drugs[2]["cas-number"], "0012345");
+        assert_eq!(
+            drugs[1]["external-identifiers"]["external-identifier"][0]["identifier"],
+            "16129704"
+        );
+        assert_eq!(drugs[2]["average-mass"], 180.16);
+        assert_eq!(drugs[2]["toxicity"], serde_json::json!({}));
+
+        let mut conversion = Conversion {
+            attribute_prefix: "".to_string(),
+            text_key: "value".to_string(),
+            empty_elements: EmptyElements::Null,
+            ..Default::default()
+        };
+        conversion
+            .types
+            .insert("average-mass".to_string(), ValueType::String);
+        conversion.arrays = vec!["drug/groups/group".to_string(), "*/polypeptide".to_string()];
+        let drugs = DrugReader::from_path(SAMPLE)
+            .unwrap()
+            .with_conversion(&conversion)
+            .collect::<Result<Vec<Value>>>()
+            .unwrap();
+
+        assert_eq!(drugs[0]["type"], "biotech");
+        assert_eq!(drugs[0]["drugbank-id"][0]["value"], "DB00001");
+        assert_eq!(drugs[0]["drugbank-id"][0]["primary"], true);
+        assert_eq!(drugs[2]["average-mass"], "180.16");
+        assert_eq!(drugs[2]["toxicity"], Value::Null);
+        assert_eq!(drugs[0]["groups"]["group"], serde_json::json!(["approved"]));
+        assert_eq!(
+            drugs[0]["targets"]["target"]["polypeptide"][0]["id"],
+            "P00734"
+        );
+        assert_eq!(
+            crate::model::primary_id_with(&drugs[0]["drugbank-id"], "", "value"),
+            "DB00001"
+        );
+    }
+
+    #[test]
+    fn test_drug_reader_latin1() {
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<drugbank><drug><name>Caf\xE9ine</name></drug></drugbank>";
+        let reader = crate::encoding::utf8_reader(std::io::Cursor::new(xml.to_vec()), "test");
+        let drugs = DrugReader::new(reader.unwrap())
+            .collect::<Result<Vec<Value>>>()
+            .unwrap();
+
+        assert_eq!(drugs[0]["name"], "Caf\u{e9}ine");
+    }
+
+    #[test]
+    fn test_xml2json() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let dir = dir.to_str().unwrap();
+
+        assert_eq!(
+            xml2json(
+                SAMPLE,
+                dir,
+                0,
+                &Conversion::default(),
+                &Quarantine::default()
+            )
+            .unwrap(),
+            3
+        );
+        let drug: Value =
+            serde_json::from_reader(File::open(format!("{}/DB00002.json", dir)).unwrap()).unwrap();
+        assert_eq!(drug["name"], "Withdrawnol");
+
+        let manifest = Manifest::load(dir).unwrap();
+        assert_eq!(
+            manifest.drugs.keys().collect::<Vec<_>>(),
+            vec!["DB00001", "DB00002", "DB00006"]
+        );
+        let bytes = std::fs::read(manifest.path(dir, "DB00006").unwrap()).unwrap();
+        assert_eq!(manifest.drugs["DB00006"].sha256, sha256(&bytes));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_xml2json_sharded() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let dir = dir.to_str().unwrap();
+        let input = format!("{}.xml", dir);
+        // the second DB00001 is a duplicate, the last drug has no id
+        std::fs::write(
+            &input,
+            r#"<drugbank>
+<drug><drugbank-id primary="true">DB00001</drugbank-id><name>a</name></drug>
+<drug><drugbank-id primary="true">DB00001</drugbank-id><name>b</name></drug>
+<drug><drugbank-id primary="true">DB00100</drugbank-id><name>c</name></drug>
+<drug><name>d</name></drug>
+</drugbank>"#,
+        )
+        .unwrap();
+
+        let quarantine = Quarantine::default();
+        assert_eq!(
+            xml2json(&input, dir, 5, &Conversion::default(), &quarantine).unwrap(),
+            2
+        );
+        assert!(Path::new(dir).join("DB000/DB00001.json").exists());
+        assert!(Path::new(dir).join("DB001/DB00100.json").exists());
+        assert_eq!(
+            Manifest::load(dir).unwrap().drugs["DB00100"].path,
+            "DB001/DB00100.json"
+        );
+
+        let failures = quarantine.failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].drugbank_id, "DB00001");
+        assert!(failures[1].error.contains("expected a primary id"));
+
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_file(input);
+    }
+
+    #[test]
+    fn test_xml2json_stream() {
+        let output = std::env::temp_dir().join(format!("{}.ndjson.zst", uuid::Uuid::new_v4()));
+        let output = output.to_str().unwrap();
+
+        assert_eq!(
+            xml2json(
+                SAMPLE,
+                output,
+                0,
+                &Conversion::default(),
+                &Quarantine::default()
+            )
+            .unwrap(),
+            3
+        );
+        let names = crate::source::records(output)
+            .unwrap()
+            .map(|f| f.unwrap().parse::<Value>().unwrap()["name"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Lepirudin", "Bivalirudin", "Withdrawnol"]);
+
+        let _ = std::fs::remove_file(output);
+    }
+
+    #[test]
+    fn test_xml2json_keys() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let json = dir.join("json");
+        let json = json.to_str().unwrap();
+        let conversion = Conversion {
+            text_key: "value".to_string(),
+            ..Default::default()
+        };
+        let quarantine = Quarantine::default();
+
+        // a stream has no manifest to record the keys in
+        let stream = dir.join("drugs.ndjson");
+        let e = xml2json(
+            SAMPLE,
+            stream.to_str().unwrap(),
+            0,
+            &conversion,
+            &quarantine,
+        );
+        assert!(matches!(e, Err(Error::Config(_))));
+
+        assert_eq!(
+            xml2json(SAMPLE, json, 0, &conversion, &quarantine).unwrap(),
+            3
+        );
+        let manifest = Manifest::load(json).unwrap();
+        assert_eq!(manifest.text_key, "value");
+        assert!(!manifest.typed());
+
+        // typed exports refuse the split instead of writing empty ids
+        let csv = dir.join("targets.csv");
+        let csv = csv.to_str().unwrap();
+        let e = crate::filter_target::drug_targets_csv(json, csv, None, &quarantine);
+        assert!(matches!(e, Err(Error::Config(_))));
+        let predicate = crate::predicate::Predicate::parse("approved").unwrap();
+        let mapping: crate::mapping::Mapping =
+            serde_yaml::from_str("columns: [{name: name, path: name}]").unwrap();
+        let e = crate::mapping::mapping_csv(json, &mapping, csv, Some(&predicate), &quarantine);
+        assert!(matches!(e, Err(Error::Config(_))));
+        // raw exports still work
+        assert_eq!(
+            crate::mapping::mapping_csv(json, &mapping, csv, None, &quarantine).unwrap(),
+            3
+        );
+        assert!(quarantine.failures().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_drug_reader_archives() {
+        let xml = std::fs::read(SAMPLE).unwrap();
+        let base = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let count = |path: &Path| DrugReader::from_path(path).unwrap().count();
+
+        for ext in ["xml.gz", "xml.zst", "xml.bz2"] {
+            let path = base.with_extension(ext);
+            let mut writer = crate::compress::Writer::create(&path).unwrap();
+            writer.write_all(&xml).unwrap();
+            writer.finish().unwrap();
+            assert_eq!(count(&path), 3);
+            let _ = std::fs::remove_file(path);
+        }
+
+        // a multi-file release, the dump isn't the first member
+        let path = base.with_extension("xml.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("README.txt", options).unwrap();
+        zip.write_all(b"DrugBank release").unwrap();
+        zip.add_directory("schema/", options).unwrap();
+        zip.start_file("schema/drugbank.xsd", options).unwrap();
+        zip.write_all(&xml[..100]).unwrap();
+        zip.start_file("full database.xml", options).unwrap();
+        zip.write_all(&xml).unwrap();
+        zip.finish().unwrap();
+        assert_eq!(count(&path), 3);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    model::Drug,
+    predicate::Predicate,
+    quarantine::{Quarantine, Stage},
+};
+use rusqlite::{params, Connection};
+
+const SCHEMA: &str = r#"
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE drugs (
+    drugbank_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    type TEXT,
+    description TEXT,
+    cas_number TEXT,
+    unii TEXT,
+    state TEXT,
+    average_mass REAL,
+    monoisotopic_mass REAL,
+    created TEXT,
+    updated TEXT
+);
+
+-- external identifiers, and the secondary drugbank ids as resource DrugBank
+CREATE TABLE identifiers (
+    drugbank_id TEXT NOT NULL REFERENCES drugs (drugbank_id),
+    resource TEXT NOT NULL,
+    identifier TEXT NOT NULL,
+    PRIMARY KEY (drugbank_id, resource, identifier)
+);
+
+CREATE TABLE groups (
+    drugbank_id TEXT NOT NULL REFERENCES drugs (drugbank_id),
+    name TEXT NOT NULL,
+    PRIMARY KEY (drugbank_id, name)
+);
+
+CREATE TABLE properties (
+    drugbank_id TEXT NOT NULL REFERENCES drugs (drugbank_id),
+    category TEXT NOT NULL CHECK (category IN ('calculated', 'experimental')),
+    kind TEXT NOT NULL,
+    value TEXT,
+    source TEXT
+);
+CREATE INDEX properties_drug ON properties (drugbank_id);
+
+CREATE TABLE targets (
+    drugbank_id TEXT NOT NULL REFERENCES drugs (drugbank_id),
+    target_id TEXT NOT NULL,
+    position INTEGER,
+    name TEXT,
+    organism TEXT,
+    actions TEXT,
+    known_action TEXT,
+    PRIMARY KEY (drugbank_id, target_id)
+);
+
+CREATE TABLE polypeptides (
+    drugbank_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    uniprot_id TEXT NOT NULL,
+    name TEXT,
+    gene_name TEXT,
+    source TEXT,
+    organism TEXT,
+    PRIMARY KEY (drugbank_id, target_id, uniprot_id),
+    FOREIGN KEY (drugbank_id, target_id) REFERENCES targets (drugbank_id, target_id)
+);
+
+CREATE TABLE interactions (
+    drugbank_id TEXT NOT NULL REFERENCES drugs (drugbank_id),
+    partner_id TEXT NOT NULL,
+    partner_name TEXT,
+    description TEXT,
+    PRIMARY KEY (drugbank_id, partner_id)
+);
+CREATE INDEX interactions_partner ON interactions (partner_id);
+"#;
+
+/// `None` for empty text, which is stored as NULL.
+fn opt(s: &str) -> Option<&str> {
+    Some(s).filter(|f| !f.is_empty())
+}
+
+fn insert_drug(tx: &Connection, drug: &Drug) -> Result<String> {
+    let id = drug.primary_id();
+    if id.is_empty() {
+        return Err(Error::value("drugbank-id", "drug without drugbank-id"));
+    }
+
+    tx.execute(
+        "INSERT INTO drugs VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            id,
+            drug.name,
+            opt(&drug.type_field),
+            opt(&drug.description),
+            opt(&drug.cas_number),
+            opt(&drug.unii),
+            drug.state.as_deref().and_then(opt),
+            drug.average_mass,
+            drug.monoisotopic_mass,
+            opt(&drug.created),
+            opt(&drug.updated),
+        ],
+    )?;
+
+    for f in &drug.external_identifiers.external_identifier {
+        tx.execute(
+            "INSERT OR IGNORE INTO identifiers VALUES (?1, ?2, ?3)",
+            params![id, f.resource, f.identifier],
+        )?;
+    }
+    // legacy BTD/APRD ids resolve to the primary one through these
+    for alias in drug.aliases() {
+        tx.execute(
+            "INSERT OR IGNORE INTO identifiers VALUES (?1, 'DrugBank', ?2)",
+            params![id, alias],
+        )?;
+    }
+
+    for group in &drug.groups.group {
+        tx.execute(
+            "INSERT OR IGNORE INTO groups VALUES (?1, ?2)",
+            params![id, group],
+        )?;
+    }
+
+    let calculated = drug
+        .calculated_properties
+        .iter()
+        .flat_map(|f| &f.property)
+        .map(|f| ("calculated", &f.kind, &f.value, &f.source));
+    let experimental = drug
+        .experimental_properties
+        .property
+        .iter()
+        .map(|f| ("experimental", &f.kind, &f.value, &f.source));
+    for (category, kind, value, source) in calculated.chain(experimental) {
+        tx.execute(
+            "INSERT INTO properties VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, category, kind, opt(value), opt(source)],
+        )?;
+    }
+
+    for t in drug.targets.target.iter().filter(|f| !f.id.is_empty()) {
+        tx.execute(
+            "INSERT OR IGNORE INTO targets VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id,
+                t.id,
+                t.position,
+                opt(&t.name),
+                opt(&t.organism),
+                opt(&t.actions.action.join("|")),
+                opt(&t.known_action),
+            ],
+        )?;
+
+        for p in t.polypeptide.iter().filter(|f| !f.id.is_empty()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO polypeptides VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    t.id,
+                    p.id,
+                    opt(&p.name),
+                    opt(&p.gene_name),
+                    opt(&p.source),
+                    opt(&p.organism.text),
+                ],
+            )?;
+        }
+    }
+
+    for f in &drug.drug_interactions.drug_interaction {
+        if f.drugbank_id.text.is_empty() {
+            continue;
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO interactions VALUES (?1, ?2, ?3, ?4)",
+            params![id, f.drugbank_id.text, opt(&f.name), opt(&f.description)],
+        )?;
+    }
+
+    Ok(id.to_string())
+}
+
+/// Writes the normalized relational schema for a stream of drugs into a
+/// fresh SQLite database at `output`, replacing any existing file. Drugs are
+/// inserted as they arrive, those that can't be are quarantined as
+/// `<drugbank id>.json`, or by their position without one, and left out.
+pub fn save_sqlite(
+    drugs: impl Iterator<Item = Drug>,
+    output: &str,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    if Path::new(output).exists() {
+        std::fs::remove_file(output).map_err(Error::io(output))?;
+    }
+
+    let mut conn = Connection::open(output).file(output)?;
+    conn.execute_batch(SCHEMA).file(output)?;
+
+    let mut tx = conn.transaction().file(output)?;
+    let mut count = 0;
+    for (index, drug) in drugs.enumerate() {
+        // a drug that fails halfway is rolled back with its savepoint
+        let sp = tx.savepoint().file(output)?;
+        match insert_drug(&sp, &drug) {
+            Ok(_) => {
+                sp.commit().file(output)?;
+                count += 1;
+            }
+            Err(e) => {
+                let id = drug.primary_id();
+                let e = e.with_drug(id);
+                let json = serde_json::to_vec(&drug).unwrap_or_default();
+                let name = match id {
+                    "" => format!("{}.json", index),
+                    id => format!("{}.json", id),
+                };
+                quarantine.record(Stage::Save, None, Some((&name, &json)), &e);
+            }
+        }
+    }
+    tx.commit().file(output)?;
+
+    Ok(count)
+}
+
+/// Exports every drug of `input`, a split directory or an NDJSON stream, that
+/// matches `predicate` into the SQLite database `output`, one record at a
+/// time. Drugs that can't be read are quarantined and skipped.
+pub fn export_sqlite(
+    input: &str,
+    output: &str,
+    predicate: Option<&Predicate>,
+    quarantine: &Quarantine,
+) -> Result<usize> {
+    // a failing source stops the stream, its error is returned once the
+    // drugs read so far are committed
+    let mut error = None;
+    let drugs = crate::source::typed_records(input)?
+        .map_while(|record| match record {
+            Ok(record) => Some(
+                record
+                    .read::<Drug>(quarantine)
+                    .filter(|drug| predicate.is_none_or(|p| p.matches(drug))),
+            ),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        })
+        .flatten();
+
+    let count = save_sqlite(drugs, output, quarantine)?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_sqlite() {
+        let mut drugs = crate::split::DrugReader::from_path("tests/fixtures/drugbank_sample.xml")
+            .unwrap()
+            .map(|f| serde_json::from_value::<Drug>(f.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        // skipped instead of failing the export
+        drugs.push(drugs[0].clone());
+        drugs.push(Drug {
+            name: "no id".to_string(),
+            ..Default::default()
+        });
+        let name = std::env::temp_dir().join(format!("{}.sqlite", uuid::Uuid::new_v4()));
+        let name = name.to_str().unwrap();
+
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let quarantine = Quarantine::new(dir.to_str(), None).unwrap();
+        assert_eq!(
+            save_sqlite(drugs.into_iter(), name, &quarantine).unwrap(),
+            3
+        );
+        let failures = quarantine.failures();
+        assert_eq!(failures.len(), 2);
+        // quarantined copies are named after the drug they hold
+        assert_eq!(failures[0].drugbank_id, "DB00001");
+        assert!(dir.join("save/DB00001.json").exists());
+        assert!(dir.join("save/4.json").exists());
+
+        let conn = Connection::open(name).unwrap();
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM drugs"), 3);
+        assert_eq!(count("SELECT COUNT(*) FROM groups"), 5);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM identifiers WHERE resource = 'DrugBank'"),
+            4
+        );
+        let primary: String = conn
+            .query_row(
+                "SELECT drugbank_id FROM identifiers WHERE resource = 'DrugBank' AND identifier = 'APRD00379'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(primary, "DB00006");
+        assert_eq!(count("SELECT COUNT(*) FROM targets"), 3);
+        assert_eq!(count("SELECT COUNT(*) FROM polypeptides"), 4);
+        assert_eq!(count("SELECT COUNT(*) FROM interactions"), 3);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM properties WHERE drugbank_id = 'DB00006'"),
+            5
+        );
+        let mass: f64 = conn
+            .query_row(
+                "SELECT average_mass FROM drugs WHERE drugbank_id = 'DB00006'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(mass, 2180.2853);
+        assert_eq!(count("SELECT COUNT(*) FROM pragma_foreign_key_check"), 0);
+
+        let _ = std::fs::remove_file(name);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,575 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::{create_dir_all, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use mongodb::bson::{Bson, Document};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    config::MongoSettings,
+    db::{BatchResult, Db},
+    error::{Error, Result, ResultExt},
+};
+
+const KEY_UPDATE_TIME: &str = "updateTime";
+const KEY_CREATE_TIME: &str = "createTime";
+
+/// Document storage the pipelines write to.
+///
+/// Filters are plain `{field: value}` documents. MongoDB evaluates them
+/// natively, the other stores compare top-level fields for equality.
+pub trait Store: Send + Sync {
+    /// Sets the fields of the document matching `filter`, inserting `doc` if
+    /// there is none.
+    fn save(&self, table: &str, filter: Document, doc: Document) -> Result<()>;
+    /// `save` that also tells whether an existing document was updated.
+    /// Stores that know it without an extra lookup override this.
+    fn upsert(&self, table: &str, filter: Document, doc: Document) -> Result<bool> {
+        let exists = self.count(table, filter.clone())? > 0;
+        self.save(table, filter, doc)?;
+        Ok(exists)
+    }
+    fn find(&self, table: &str, filter: Document) -> Result<Vec<Document>>;
+    fn count(&self, table: &str, filter: Document) -> Result<u64>;
+    /// Removes every matching document, returns how many were removed.
+    fn delete(&self, table: &str, filter: Document) -> Result<u64>;
+
+    /// Saves `(filter, doc)` pairs `batch_size` at a time, returning the
+    /// counts of every batch. Failing records are logged and counted with
+    /// their error, they don't stop the batch. Stores that can stop partway return
+    /// `Error::PartialSave` with the counts of the batches already written.
+    fn save_many(
+        &self,
+        table: &str,
+        records: Vec<(Document, Document)>,
+        batch_size: usize,
+    ) -> Result<Vec<BatchResult>> {
+        let mut results = vec![];
+        for batch in records.chunks(batch_size.max(1)) {
+            let mut result = BatchResult::default();
+            for (i, (filter, doc)) in batch.iter().enumerate() {
+                match self.upsert(table, filter.clone(), doc.clone()) {
+                    Ok(true) => result.modified += 1,
+                    Ok(false) => result.inserted += 1,
+                    Err(e) => {
+                        log::warn!("save {:?} error : {}", filter, e);
+                        result.refuse(i, e.to_string());
+                    }
+                }
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+/// Opens a store from a location string:
+/// `mongodb://…`, `sqlite:<file>`, `jsonl:<dir>` or `memory`. MongoDB
+/// connections take everything but the url from `mongo`.
+pub fn open_store(location: &str, mongo: &MongoSettings) -> Result<Box<dyn Store>> {
+    if location.starts_with("mongodb://") || location.starts_with("mongodb+srv://") {
+        crate::db::init_db(&MongoSettings {
+            url: location.to_string(),
+            ..mongo.clone()
+        })?;
+        Ok(Box::new(MongoStore))
+    } else if let Some(path) = location.strip_prefix("sqlite:") {
+        Ok(Box::new(SqliteStore::open(path)?))
+    } else if let Some(path) = location.strip_prefix("jsonl:") {
+        Ok(Box::new(JsonLinesStore::open(path)?))
+    } else if location == "memory" {
+        Ok(Box::new(MemoryStore::default()))
+    } else {
+        Err(Error::Config(format!("unknown store {}", location)))
+    }
+}
+
+fn matches(doc: &Document, filter: &Document) -> bool {
+    filter.iter().all(|(k, v)| doc.get(k) == Some(v))
+}
+
+fn now() -> Bson {
+    Bson::DateTime(mongodb::bson::DateTime::now())
+}
+
+/// Applies `update` the way `Db::save` does: `$set` on an existing document,
+/// or a new document stamped with its creation time.
+fn updated(existing: Option<Document>, update: Document) -> Document {
+    let date = now();
+    match existing {
+        Some(mut doc) => {
+            doc.extend(update);
+            doc.insert(KEY_UPDATE_TIME, date);
+            doc
+        }
+        None => {
+            let mut doc = update;
+            doc.insert(KEY_UPDATE_TIME, date.clone());
+            doc.insert(KEY_CREATE_TIME, date);
+            doc
+        }
+    }
+}
+
+/// `updated` on the first document of `docs` matching `filter`, or a new one
+/// pushed at the end. Returns whether a document was updated.
+fn upsert(docs: &mut Vec<Document>, filter: &Document, update: Document) -> bool {
+    match docs.iter_mut().find(|f| matches(f, filter)) {
+        Some(existing) => {
+            *existing = updated(Some(std::mem::take(existing)), update);
+            true
+        }
+        None => {
+            docs.push(updated(None, update));
+            false
+        }
+    }
+}
+
+/// MongoDB through the `Db` singleton, `init_db` has to be called first.
+#[derive(Debug, Clone, Default)]
+pub struct MongoStore;
+
+impl Store for MongoStore {
+    fn save(&self, table: &str, filter: Document, doc: Document) -> Result<()> {
+        Db::save(table, filter, doc)
+    }
+
+    fn save_many(
+        &self,
+        table: &str,
+        records: Vec<(Document, Document)>,
+        batch_size: usize,
+    ) -> Result<Vec<BatchResult>> {
+        Db::save_many(table, records, batch_size)
+    }
+
+    fn find(&self, table: &str, filter: Document) -> Result<Vec<Document>> {
+        let found = Mutex::new(vec![]);
+        Db::find(table, filter, None, &|d: Document| {
+            found.lock().unwrap().push(d)
+        })?;
+
+        Ok(found.into_inner().unwrap())
+    }
+
+    fn count(&self, table: &str, filter: Document) -> Result<u64> {
+        Ok(Db::count(table, filter))
+    }
+
+    fn delete(&self, table: &str, filter: Document) -> Result<u64> {
+        Ok(Db::delete_many(table, filter)?)
+    }
+}
+
+/// Keeps everything in memory, for tests and dry runs.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    tables: Mutex<HashMap<String, Vec<Document>>>,
+}
+
+impl Store for MemoryStore {
+    fn save(&self, table: &str, filter: Document, doc: Document) -> Result<()> {
+        self.upsert(table, filter, doc).map(|_| ())
+    }
+
+    fn upsert(&self, table: &str, filter: Document, doc: Document) -> Result<bool> {
+        let mut tables = self.tables.lock().unwrap();
+        Ok(upsert(
+            tables.entry(table.to_string()).or_default(),
+            &filter,
+            doc,
+        ))
+    }
+
+    fn find(&self, table: &str, filter: Document) -> Result<Vec<Document>> {
+        let tables = self.tables.lock().unwrap();
+        Ok(tables
+            .get(table)
+            .map(|docs| {
+                docs.iter()
+                    .filter(|f| matches(f, &filter))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn count(&self, table: &str, filter: Document) -> Result<u64> {
+        Ok(self.find(table, filter)?.len() as u64)
+    }
+
+    fn delete(&self, table: &str, filter: Document) -> Result<u64> {
+        let mut tables = self.tables.lock().unwrap();
+        let docs = match tables.get_mut(table) {
+            Some(d) => d,
+            None => return Ok(0),
+        };
+        let before = docs.len();
+        docs.retain(|f| !matches(f, &filter));
+        Ok((before - docs.len()) as u64)
+    }
+}
+
+/// One `<table>.jsonl` file per collection inside a directory, documents are
+/// stored as relaxed extended json.
+#[derive(Debug)]
+pub struct JsonLinesStore {
+    dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonLinesStore {
+    pub fn open(dir: &str) -> Result<Self> {
+        create_dir_all(dir).map_err(Error::io(dir))?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn path(&self, table: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", table))
+    }
+
+    fn read(&self, table: &str) -> Result<Vec<Document>> {
+        let path = self.path(table);
+        let file = match File::open(&path) {
+            Ok(f) => f,
+            Err(_) => return Ok(vec![]),
+        };
+
+        let mut docs = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(Error::io(&path))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let name = path.display().to_string();
+            let value: serde_json::Value = serde_json::from_str(&line).file(&name)?;
+            match Bson::try_from(value).file(&name)? {
+                Bson::Document(d) => docs.push(d),
+                _ => return Err(Error::value("", "line isn't a document")).file(&name),
+            }
+        }
+
+        Ok(docs)
+    }
+
+    fn write(&self, table: &str, docs: &[Document], append: bool) -> Result<()> {
+        let path = self.path(table);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&path)
+            .map_err(Error::io(&path))?;
+
+        for doc in docs {
+            let line = Bson::Document(doc.clone()).into_relaxed_extjson();
+            writeln!(file, "{}", line).map_err(Error::io(&path))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl JsonLinesStore {
+    /// Upserts `records` into the documents read from `table` and writes them
+    /// back once, appending when nothing existing changed.
+    fn save_all(
+        &self,
+        table: &str,
+        docs: &mut Vec<Document>,
+        records: &[(Document, Document)],
+    ) -> Result<BatchResult> {
+        let len = docs.len();
+        let mut result = BatchResult::default();
+        for (filter, doc) in records {
+            if upsert(docs, filter, doc.clone()) {
+                result.modified += 1;
+            } else {
+                result.inserted += 1;
+            }
+        }
+
+        if result.modified == 0 {
+            // only new documents, no need to rewrite the file
+            self.write(table, &docs[len..], true)?;
+        } else {
+            self.write(table, docs, false)?;
+        }
+
+        Ok(result)
+    }
+}
+
+impl Store for JsonLinesStore {
+    fn save(&self, table: &str, filter: Document, doc: Document) -> Result<()> {
+        self.upsert(table, filter, doc).map(|_| ())
+    }
+
+    fn upsert(&self, table: &str, filter: Document, doc: Document) -> Result<bool> {
+        let _lock = self.lock.lock().unwrap();
+        let mut docs = self.read(table)?;
+        let result = self.save_all(table, &mut docs, &[(filter, doc)])?;
+        Ok(result.modified > 0)
+    }
+
+    /// Reads the table once and writes it once per batch.
+    fn save_many(
+        &self,
+        table: &str,
+        records: Vec<(Document, Document)>,
+        batch_size: usize,
+    ) -> Result<Vec<BatchResult>> {
+        let _lock = self.lock.lock().unwrap();
+        let mut docs = self.read(table)?;
+        records
+            .chunks(batch_size.max(1))
+            .map(|batch| self.save_all(table, &mut docs, batch))
+            .collect()
+    }
+
+    fn find(&self, table: &str, filter: Document) -> Result<Vec<Document>> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self
+            .read(table)?
+            .into_iter()
+            .filter(|f| matches(f, &filter))
+            .collect())
+    }
+
+    fn count(&self, table: &str, filter: Document) -> Result<u64> {
+        Ok(self.find(table, filter)?.len() as u64)
+    }
+
+    fn delete(&self, table: &str, filter: Document) -> Result<u64> {
+        let _lock = self.lock.lock().unwrap();
+        let mut docs = self.read(table)?;
+        let before = docs.len();
+        docs.retain(|f| !matches(f, &filter));
+        self.write(table, &docs, false)?;
+        Ok((before - docs.len()) as u64)
+    }
+}
+
+/// Documents as BSON blobs in a single SQLite table. Each document is keyed
+/// by the filter it was saved with, so saving looks it up by index.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS documents (
+                id INTEGER PRIMARY KEY,
+                collection TEXT NOT NULL,
+                key TEXT NOT NULL,
+                doc BLOB NOT NULL,
+                UNIQUE (collection, key)
+            );",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn rows(conn: &Connection, table: &str) -> Result<Vec<(i64, Document)>> {
+        let mut stmt =
+            conn.prepare("SELECT id, doc FROM documents WHERE collection = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![table], |r| {
+            Ok((r.get::<_, i64>(0)?, r.get::<_, Vec<u8>>(1)?))
+        })?;
+
+        let mut docs = vec![];
+        for row in rows {
+            let (id, blob) = row?;
+            let doc = Document::from_reader(&mut blob.as_slice())?;
+            docs.push((id, doc));
+        }
+
+        Ok(docs)
+    }
+
+    fn blob(doc: &Document) -> Result<Vec<u8>> {
+        let mut blob = vec![];
+        doc.to_writer(&mut blob)?;
+        Ok(blob)
+    }
+
+    /// Key of the document saved with `filter`, its extended json.
+    fn key(filter: &Document) -> String {
+        Bson::Document(filter.clone())
+            .into_relaxed_extjson()
+            .to_string()
+    }
+
+    fn upsert_in(conn: &Connection, table: &str, filter: &Document, doc: Document) -> Result<bool> {
+        let key = Self::key(filter);
+        let existing: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT doc FROM documents WHERE collection = ?1 AND key = ?2",
+                params![table, key],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let existing = match existing {
+            Some(blob) => Some(Document::from_reader(&mut blob.as_slice())?),
+            None => None,
+        };
+        let exists = existing.is_some();
+
+        conn.execute(
+            "INSERT INTO documents (collection, key, doc) VALUES (?1, ?2, ?3)
+             ON CONFLICT (collection, key) DO UPDATE SET doc = excluded.doc",
+            params![table, key, Self::blob(&updated(existing, doc))?],
+        )?;
+
+        Ok(exists)
+    }
+}
+
+impl Store for SqliteStore {
+    fn save(&self, table: &str, filter: Document, doc: Document) -> Result<()> {
+        self.upsert(table, filter, doc).map(|_| ())
+    }
+
+    fn upsert(&self, table: &str, filter: Document, doc: Document) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Self::upsert_in(&conn, table, &filter, doc)
+    }
+
+    /// One transaction per batch.
+    fn save_many(
+        &self,
+        table: &str,
+        records: Vec<(Document, Document)>,
+        batch_size: usize,
+    ) -> Result<Vec<BatchResult>> {
+        let mut conn = self.conn.lock().unwrap();
+        let mut results = vec![];
+        for batch in records.chunks(batch_size.max(1)) {
+            let tx = conn.transaction()?;
+            let mut result = BatchResult::default();
+            for (i, (filter, doc)) in batch.iter().enumerate() {
+                match Self::upsert_in(&tx, table, filter, doc.clone()) {
+                    Ok(true) => result.modified += 1,
+                    Ok(false) => result.inserted += 1,
+                    Err(e) => {
+                        log::warn!("save {:?} error : {}", filter, e);
+                        result.refuse(i, e.to_string());
+                    }
+                }
+            }
+            tx.commit()?;
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    fn find(&self, table: &str, filter: Document) -> Result<Vec<Document>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(Self::rows(&conn, table)?
+            .into_iter()
+            .map(|(_, d)| d)
+            .filter(|f| matches(f, &filter))
+            .collect())
+    }
+
+    fn count(&self, table: &str, filter: Document) -> Result<u64> {
+        Ok(self.find(table, filter)?.len() as u64)
+    }
+
+    fn delete(&self, table: &str, filter: Document) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let mut count = 0;
+        for (id, d) in Self::rows(&conn, table)? {
+            if matches(&d, &filter) {
+                conn.execute("DELETE FROM documents WHERE id = ?1", params![id])?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mongodb::bson::doc;
+
+    fn check(store: &dyn Store) {
+        store
+            .save("t", doc! {"id": "a"}, doc! {"id": "a", "v": 1})
+            .unwrap();
+        store
+            .save("t", doc! {"id": "b"}, doc! {"id": "b", "v": 2})
+            .unwrap();
+        store
+            .save("t", doc! {"id": "a"}, doc! {"id": "a", "v": 3})
+            .unwrap();
+
+        assert_eq!(store.count("t", doc! {}).unwrap(), 2);
+        assert_eq!(store.count("other", doc! {}).unwrap(), 0);
+
+        let a = store.find("t", doc! {"id": "a"}).unwrap();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].get_i32("v").unwrap(), 3);
+        assert!(a[0].get_datetime(KEY_CREATE_TIME).is_ok());
+
+        assert_eq!(store.delete("t", doc! {"id": "b"}).unwrap(), 1);
+        assert_eq!(store.count("t", doc! {}).unwrap(), 1);
+
+        let records = ["a", "c", "d"]
+            .iter()
+            .map(|id| (doc! {"id": id}, doc! {"id": id, "v": 4}))
+            .collect();
+        let results = store.save_many("t", records, 2).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                BatchResult {
+                    inserted: 1,
+                    modified: 1,
+                    failed: 0,
+                    errors: vec![],
+                },
+                BatchResult {
+                    inserted: 1,
+                    modified: 0,
+                    failed: 0,
+                    errors: vec![],
+                }
+            ]
+        );
+        assert_eq!(store.count("t", doc! {"v": 4}).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_stores() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let dir = dir.to_str().unwrap();
+
+        check(&MemoryStore::default());
+        let mongo = MongoSettings::default();
+        check(&*open_store(&format!("jsonl:{}", dir), &mongo).unwrap());
+        check(&*open_store(&format!("sqlite:{}.sqlite", dir), &mongo).unwrap());
+        assert!(open_store("redis://localhost", &mongo).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_file(format!("{}.sqlite", dir));
+    }
+}
@@ -0,0 +1,328 @@
+use std::{fs::File, path::Path, sync::Arc};
+
+use arrow_array::{
+    builder::{BooleanBuilder, Float64Builder, Int64Builder, ListBuilder, StringBuilder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field as ArrowField, Schema};
+use csv::{QuoteStyle, WriterBuilder};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+
+use crate::error::{Error, Result, ResultExt};
+
+/// Rows per parquet record batch.
+const BATCH_ROWS: usize = 8192;
+
+/// Separator list cells are joined with in CSV output.
+const LIST_SEPARATOR: &str = "|";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Utf8,
+    Float64,
+    Int64,
+    Boolean,
+    /// List of strings
+    List,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub kind: Kind,
+}
+
+impl Field {
+    pub fn new(name: &str, kind: Kind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+        }
+    }
+
+    fn arrow(&self) -> ArrowField {
+        let data_type = match self.kind {
+            Kind::Utf8 => DataType::Utf8,
+            Kind::Float64 => DataType::Float64,
+            Kind::Int64 => DataType::Int64,
+            Kind::Boolean => DataType::Boolean,
+            Kind::List => DataType::List(Arc::new(ArrowField::new("item", DataType::Utf8, true))),
+        };
+        ArrowField::new(&self.name, data_type, true)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Str(String),
+    F64(f64),
+    I64(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Cell {
+    /// Empty strings become nulls.
+    pub fn text(s: &str) -> Self {
+        if s.is_empty() {
+            Cell::Null
+        } else {
+            Cell::Str(s.to_string())
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Cell::Null => String::new(),
+            Cell::Str(s) => s.clone(),
+            Cell::F64(f) => f.to_string(),
+            Cell::I64(i) => i.to_string(),
+            Cell::Bool(b) => b.to_string(),
+            Cell::List(l) => l.join(LIST_SEPARATOR),
+        }
+    }
+}
+
+/// A row type that can be written as CSV or Parquet.
+pub trait Tabular {
+    fn fields() -> Vec<Field>;
+    fn cells(&self) -> Vec<Cell>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Parquet,
+}
+
+impl Format {
+    /// `.parquet` files get Parquet, everything else CSV.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension() {
+            Some(e) if e == "parquet" => Format::Parquet,
+            _ => Format::Csv,
+        }
+    }
+}
+
+/// Column layout plus rows, for exports whose columns are only known at runtime.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub fields: Vec<Field>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn from_rows<T: Tabular>(rows: &[T]) -> Self {
+        Self {
+            fields: T::fields(),
+            rows: rows.iter().map(|f| f.cells()).collect(),
+        }
+    }
+
+    /// Writes the table in the format picked by the extension of `name`.
+    pub fn save(&self, name: &str) -> Result<()> {
+        match Format::from_path(name) {
+            Format::Csv => self.save_csv(name),
+            Format::Parquet => self.save_parquet(name),
+        }
+        .file(name)
+    }
+
+    fn save_csv(&self, name: &str) -> Result<()> {
+        let mut writer = WriterBuilder::new()
+            .quote_style(QuoteStyle::Necessary)
+            .from_path(name)?;
+
+        writer.write_record(self.fields.iter().map(|f| &f.name))?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(|f| f.csv()))?;
+        }
+
+        writer.flush().map_err(Error::io(name))?;
+
+        Ok(())
+    }
+
+    fn save_parquet(&self, name: &str) -> Result<()> {
+        let schema = Arc::new(Schema::new(
+            self.fields.iter().map(|f| f.arrow()).collect::<Vec<_>>(),
+        ));
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(
+            File::create(name).map_err(Error::io(name))?,
+            schema.clone(),
+            Some(props),
+        )?;
+
+        for rows in self.rows.chunks(BATCH_ROWS) {
+            let columns = self
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| column(f, rows.iter().map(|r| &r[i])))
+                .collect::<Result<Vec<ArrayRef>>>()?;
+
+            writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+        }
+
+        writer.close()?;
+
+        Ok(())
+    }
+}
+
+fn mismatch(field: &Field, cell: &Cell) -> Error {
+    Error::Schema(format!("column {} can't hold {:?}", field.name, cell))
+}
+
+fn column<'a>(field: &Field, cells: impl Iterator<Item = &'a Cell>) -> Result<ArrayRef> {
+    Ok(match field.kind {
+        Kind::Utf8 => {
+            let mut b = StringBuilder::new();
+            for c in cells {
+                match c {
+                    Cell::Null => b.append_null(),
+                    Cell::Str(s) => b.append_value(s),
+                    c => b.append_value(c.csv()),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        Kind::Float64 => {
+            let mut b = Float64Builder::new();
+            for c in cells {
+                match c {
+                    Cell::Null => b.append_null(),
+                    Cell::F64(f) => b.append_value(*f),
+                    Cell::I64(i) => b.append_value(*i as f64),
+                    c => return Err(mismatch(field, c)),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        Kind::Int64 => {
+            let mut b = Int64Builder::new();
+            for c in cells {
+                match c {
+                    Cell::Null => b.append_null(),
+                    Cell::I64(i) => b.append_value(*i),
+                    c => return Err(mismatch(field, c)),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        Kind::Boolean => {
+            let mut b = BooleanBuilder::new();
+            for c in cells {
+                match c {
+                    Cell::Null => b.append_null(),
+                    Cell::Bool(v) => b.append_value(*v),
+                    c => return Err(mismatch(field, c)),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        Kind::List => {
+            let mut b = ListBuilder::new(StringBuilder::new());
+            for c in cells {
+                match c {
+                    Cell::Null => b.append_null(),
+                    Cell::List(l) => b.append_value(l.iter().map(Some)),
+                    c => return Err(mismatch(field, c)),
+                }
+            }
+            Arc::new(b.finish())
+        }
+    })
+}
+
+/// Writes typed rows as CSV or Parquet depending on the extension of `name`.
+pub fn save_to_file<T: Tabular>(name: &str, v: &[T]) -> Result<()> {
+    Table::from_rows(v).save(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Array, Float64Array, ListArray, StringArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn table() -> Table {
+        Table {
+            fields: vec![
+                Field::new("id", Kind::Utf8),
+                Field::new("weight", Kind::Float64),
+                Field::new("groups", Kind::List),
+            ],
+            rows: vec![
+                vec![
+                    Cell::text("DB00006"),
+                    Cell::F64(2180.2853),
+                    Cell::List(vec!["approved".to_string(), "investigational".to_string()]),
+                ],
+                vec![Cell::text("DB00001"), Cell::Null, Cell::List(vec![])],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_save_csv() {
+        let name = std::env::temp_dir().join(format!("{}.csv", uuid::Uuid::new_v4()));
+        let name = name.to_str().unwrap();
+
+        table().save(name).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(name).unwrap(),
+            "id,weight,groups\nDB00006,2180.2853,approved|investigational\nDB00001,,\n"
+        );
+
+        let _ = std::fs::remove_file(name);
+    }
+
+    #[test]
+    fn test_save_parquet() {
+        let name = std::env::temp_dir().join(format!("{}.parquet", uuid::Uuid::new_v4()));
+        let name = name.to_str().unwrap();
+
+        table().save(name).unwrap();
+
+        let batch = ParquetRecordBatchReaderBuilder::try_new(File::open(name).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let id = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(id.value(1), "DB00001");
+
+        let weight = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(weight.value(0), 2180.2853);
+        assert!(weight.is_null(1));
+
+        let groups = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(groups.value(0).len(), 2);
+        assert_eq!(groups.value(1).len(), 0);
+
+        let _ = std::fs::remove_file(name);
+    }
+}